@@ -1,5 +1,12 @@
-use crate::{license, set_working_dir, value_or_default};
-use anyhow::Result;
+use crate::{
+    changes::{Changes, Conflict},
+    ci, header, license, manifest,
+    new::Preset,
+    set_working_dir,
+    template::{self, Variables},
+    value_or_default,
+};
+use anyhow::{ensure, Result};
 use std::path;
 
 /// Add useful content to your Rust project.
@@ -11,12 +18,28 @@ pub struct Add {
     /// Add licenses to the project.
     #[clap(long)]
     licenses: bool,
+    /// SPDX license expression of the licenses to add.
+    ///
+    /// One license file is generated for each license of the expression.
+    /// Implies `--licenses` unless used with `--headers`. Defaults to
+    /// `MIT OR Apache-2.0`.
+    #[clap(long)]
+    license: Option<String>,
+    /// Add SPDX and copyright headers to the Rust files of the project.
+    ///
+    /// The license expression is taken from `--license`, or from the
+    /// manifest of the project.
+    #[clap(long)]
+    headers: bool,
     /// Full name used in the licenses.
     #[clap(long)]
     full_name: Option<String>,
     /// Add CI to the project.
     ///
-    /// Generate needed files for a binary.
+    /// Generate the PR, main and release workflows for a binary, the release
+    /// workflow is skipped when no binary is found. Use `--lib` to generate
+    /// them for a library, with a release workflow that publishes the crate
+    /// on tags.
     #[clap(long)]
     ci: bool,
     /// Add CI for a library instead of a binary.
@@ -28,23 +51,234 @@ pub struct Add {
     /// Do not add CI tests for OSX.
     #[clap(long)]
     no_osx: bool,
+    /// Add a README to the project.
+    #[clap(long)]
+    readme: bool,
+    /// Add a `.gitignore` to the project.
+    #[clap(long)]
+    gitignore: bool,
+    /// Add a release workflow that builds the binary on tags.
+    #[clap(long)]
+    release: bool,
+    /// Targets built by the release workflow.
+    #[clap(long = "target")]
+    targets: Vec<String>,
+    /// Show the files that would be created or modified without writing
+    /// them.
+    #[clap(long)]
+    dry_run: bool,
+    /// Overwrite the existing files.
+    #[clap(long, conflicts_with_all = &["skip-existing", "backup"])]
+    force: bool,
+    /// Leave the existing files untouched.
+    #[clap(long, conflicts_with = "backup")]
+    skip_existing: bool,
+    /// Move the existing files to `<file>.bak` before overwriting them.
+    ///
+    /// Existing backups are kept, `<file>.bak.1`, `<file>.bak.2`, ... are used
+    /// instead.
+    #[clap(long)]
+    backup: bool,
+    /// Add the licenses and the headers to every publishable member of the
+    /// workspace instead of its root.
+    #[clap(long)]
+    per_member: bool,
+    /// Add the licenses and the headers to the given members of the
+    /// workspace.
+    ///
+    /// The release workflow is generated for the package if only one is
+    /// given.
+    #[clap(long = "package")]
+    packages: Vec<String>,
 }
 
 impl Add {
-    pub fn run(self, default_full_name: Option<String>) -> Result<()> {
+    /// Path of the project given to the command.
+    pub fn path(&self) -> Option<&path::Path> {
+        self.path.as_deref()
+    }
+
+    /// Create the arguments adding the content of a preset to a new project.
+    pub fn from_preset(path: path::PathBuf, lib: bool, preset: Preset) -> Self {
+        Self {
+            path: Some(path),
+            licenses: preset.licenses,
+            license: preset.license,
+            headers: preset.headers,
+            full_name: None,
+            ci: preset.ci,
+            lib,
+            no_windows: preset.no_windows,
+            no_osx: preset.no_osx,
+            readme: preset.readme,
+            gitignore: preset.gitignore,
+            release: false,
+            targets: preset.release_targets,
+            dry_run: false,
+            force: false,
+            skip_existing: false,
+            backup: false,
+            per_member: false,
+            packages: Vec::new(),
+        }
+    }
+
+    pub fn run(
+        self,
+        default_full_name: Option<String>,
+        default_release_targets: Vec<String>,
+    ) -> Result<()> {
         let working_dir = set_working_dir(self.path)?;
 
-        let full_name = value_or_default(self.full_name, default_full_name, "full_name")?;
+        let licenses = self.licenses || (self.license.is_some() && !self.headers);
 
-        if self.licenses {
-            log::info!("Generating licenses");
-            license::add_licenses(&working_dir, full_name)?;
-        } else if self.ci {
-            todo!("Generating CI files");
-        } else {
+        if !licenses
+            && !self.headers
+            && !self.ci
+            && !self.release
+            && !self.readme
+            && !self.gitignore
+        {
             log::error!("Please select something to add");
+            return Ok(());
+        }
+        ensure!(
+            !(self.ci && self.lib && self.release),
+            "`--release` cannot be used with `--ci --lib`, both generate a release workflow"
+        );
+
+        let full_name = if licenses || self.headers {
+            value_or_default(self.full_name, default_full_name, "full_name")?
+        } else {
+            self.full_name.or(default_full_name).unwrap_or_default()
+        };
+        let per_member = self.per_member || !self.packages.is_empty();
+        let project_dirs = if per_member {
+            manifest::members(&working_dir, &self.packages)?
+        } else {
+            vec![working_dir.clone()]
+        };
+        let variables = Variables::new(&working_dir, full_name);
+        let mut changes = Changes::new();
+
+        for project_dir in &project_dirs {
+            let variables = if per_member {
+                log::info!("Adding content to {}", project_dir.display());
+                Variables::new(project_dir, variables.full_name.clone())
+            } else {
+                variables.clone()
+            };
+
+            if licenses {
+                let expression = self
+                    .license
+                    .clone()
+                    .unwrap_or_else(|| license::DEFAULT_LICENSE_EXPRESSION.to_string());
+
+                let licenses = license::parse_expression(&expression)?;
+
+                log::info!("Generating licenses");
+                let file_names =
+                    license::add_licenses(&mut changes, project_dir, &variables, &licenses)?;
+
+                if project_dir.join("Cargo.toml").exists() {
+                    manifest::update_license(
+                        &mut changes,
+                        project_dir,
+                        &expression,
+                        &file_names,
+                        !per_member,
+                    )?;
+                }
+            }
+
+            if self.headers {
+                let expression = match &self.license {
+                    Some(expression) => expression.clone(),
+                    None => manifest::license(project_dir)?
+                        .unwrap_or_else(|| license::DEFAULT_LICENSE_EXPRESSION.to_string()),
+                };
+
+                log::info!("Adding headers");
+                header::add_headers(&mut changes, project_dir, &variables.full_name, &expression)?;
+            }
         }
 
-        Ok(())
+        if self.readme {
+            log::info!("Generating README");
+            changes.create(
+                working_dir.join("README.md"),
+                template::render("readme", &variables)?,
+            );
+        }
+
+        if self.gitignore {
+            log::info!("Generating .gitignore");
+            changes.create(
+                working_dir.join(".gitignore"),
+                template::render("gitignore", &variables)?,
+            );
+        }
+
+        if self.ci {
+            log::info!("Generating CI files");
+            ci::add_ci(
+                &mut changes,
+                &working_dir,
+                &variables,
+                self.lib,
+                self.no_windows,
+                self.no_osx,
+            )?;
+        }
+
+        if self.release || (self.ci && !self.lib) {
+            let targets = if !self.targets.is_empty() {
+                self.targets
+            } else if !default_release_targets.is_empty() {
+                default_release_targets
+            } else {
+                vec!["x86_64-unknown-linux-gnu".to_string()]
+            };
+
+            let package_dir = match project_dirs.as_slice() {
+                [package_dir] => package_dir,
+                _ => &working_dir,
+            };
+
+            // The release workflow is only added by `--ci` when there is a
+            // binary to release.
+            let bin = match ci::find_bin(package_dir) {
+                Ok(bin) => Some(bin),
+                Err(err) if !self.release => {
+                    log::info!("Skipping the release workflow: {}", err);
+                    None
+                }
+                Err(err) => return Err(err),
+            };
+
+            if let Some(bin) = bin {
+                log::info!("Generating release workflow");
+                ci::add_release(
+                    &mut changes,
+                    &working_dir,
+                    &bin,
+                    &Variables::new(package_dir, variables.full_name.clone()),
+                    targets,
+                )?;
+            }
+        }
+
+        let conflict = if self.force {
+            Conflict::Force
+        } else if self.skip_existing {
+            Conflict::Skip
+        } else if self.backup {
+            Conflict::Backup
+        } else {
+            Conflict::Refuse
+        };
+
+        changes.apply(self.dry_run, conflict)
     }
 }
@@ -0,0 +1,193 @@
+use anyhow::{bail, Result};
+use similar::TextDiff;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Files written by the generators of `yoz add`.
+///
+/// Generators register the content of the files they produce and nothing is
+/// written on the disk until the changes are applied.
+#[derive(Debug, Default)]
+pub struct Changes {
+    files: Vec<File>,
+}
+
+#[derive(Debug)]
+struct File {
+    path: PathBuf,
+    content: String,
+    generated: bool,
+}
+
+impl Changes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a generated file.
+    ///
+    /// If a different file already exists at this path, it is handled
+    /// according to the conflict policy when the changes are applied.
+    pub fn create(&mut self, path: impl Into<PathBuf>, content: impl Into<String>) {
+        self.insert(path.into(), content.into(), true);
+    }
+
+    /// Register the new content of an existing file that is edited in place.
+    pub fn write(&mut self, path: impl Into<PathBuf>, content: impl Into<String>) {
+        self.insert(path.into(), content.into(), false);
+    }
+
+    fn insert(&mut self, path: PathBuf, content: String, generated: bool) {
+        match self.files.iter_mut().find(|file| file.path == path) {
+            Some(file) => file.content = content,
+            None => self.files.push(File {
+                path,
+                content,
+                generated,
+            }),
+        }
+    }
+
+    /// Read a file, taking into account the changes registered so far.
+    pub fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.files.iter().find(|file| file.path == path) {
+            Some(file) => Ok(file.content.clone()),
+            None => fs::read_to_string(path),
+        }
+    }
+
+    /// Write the files that changed.
+    ///
+    /// Generated files replacing a different existing file are conflicts,
+    /// handled according to `conflict`. With `dry_run`, nothing is written
+    /// and a unified diff of every file that would be created or modified is
+    /// printed instead.
+    pub fn apply(self, dry_run: bool, conflict: Conflict) -> Result<()> {
+        let conflicts = self
+            .files
+            .iter()
+            .filter(|file| file.is_conflict())
+            .map(|file| format!("  {}", file.path.display()))
+            .collect::<Vec<String>>();
+
+        if !dry_run && conflict == Conflict::Refuse && !conflicts.is_empty() {
+            bail_conflicts(&conflicts)?;
+        }
+
+        for file in self.files {
+            let current = fs::read_to_string(&file.path).ok();
+
+            if current.as_ref() == Some(&file.content) {
+                log::debug!("{} is up to date", file.path.display());
+                continue;
+            }
+
+            let is_conflict = file.is_conflict();
+            if is_conflict && conflict == Conflict::Skip {
+                log::info!("Skipping existing {}", file.path.display());
+                continue;
+            }
+
+            let backup_path = if is_conflict && conflict == Conflict::Backup {
+                Some(backup_path(&file.path))
+            } else {
+                None
+            };
+
+            if dry_run {
+                let path = file.path.display().to_string();
+                let (header, old_path, current) = match &current {
+                    Some(current) => ("Would modify", path.as_str(), current.as_str()),
+                    None => ("Would create", "/dev/null", ""),
+                };
+
+                println!("{} {}", header, path);
+                if let Some(backup_path) = &backup_path {
+                    println!("Would move the original to {}", backup_path.display());
+                }
+                print!(
+                    "{}",
+                    TextDiff::from_lines(current, &file.content)
+                        .unified_diff()
+                        .header(old_path, &path)
+                );
+            } else {
+                if let Some(backup_path) = &backup_path {
+                    log::info!(
+                        "Moving {} to {}",
+                        file.path.display(),
+                        backup_path.display()
+                    );
+                    fs::rename(&file.path, backup_path)?;
+                }
+
+                if current.is_some() {
+                    log::info!("Updating {}", file.path.display());
+                } else {
+                    log::info!("Creating {}", file.path.display());
+                }
+
+                if let Some(parent) = file.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&file.path, file.content)?;
+            }
+        }
+
+        if conflict == Conflict::Refuse && !conflicts.is_empty() {
+            bail_conflicts(&conflicts)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl File {
+    fn is_conflict(&self) -> bool {
+        self.generated
+            && self.path.exists()
+            && fs::read_to_string(&self.path).ok().as_ref() != Some(&self.content)
+    }
+}
+
+/// Find a backup path that does not exist yet: `<file>.bak`, then
+/// `<file>.bak.1`, `<file>.bak.2`, ...
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    let backup_path = PathBuf::from(backup_path);
+
+    let mut candidate = backup_path.clone();
+    let mut i = 0;
+    while candidate.exists() {
+        i += 1;
+        let mut numbered = backup_path.clone().into_os_string();
+        numbered.push(format!(".{}", i));
+        candidate = PathBuf::from(numbered);
+    }
+
+    candidate
+}
+
+fn bail_conflicts(conflicts: &[String]) -> Result<()> {
+    bail!(
+        "the following files already exist:\n{}\n\
+        use `--force`, `--skip-existing` or `--backup` to choose what to do with them",
+        conflicts.join("\n")
+    );
+}
+
+/// What to do when a generated file already exists.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Conflict {
+    /// Refuse to write anything.
+    Refuse,
+    /// Overwrite the existing file.
+    Force,
+    /// Leave the existing file.
+    Skip,
+    /// Move the existing file to `<file>.bak` before writing.
+    Backup,
+}
@@ -1,9 +1,48 @@
-use crate::{set_working_dir, values_or_default};
-use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::{path, process, time};
+use crate::{
+    diagnostics::{self, Diagnostic},
+    history::History,
+    manifest,
+    report::{self, ReportFormat},
+    set_working_dir, values_or_default,
+    watch::Watcher,
+};
+use anyhow::{anyhow, bail, ensure, Result};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    io::Read,
+    mem, path, process,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread, time,
+};
+
+/// Exit code used when a check failed.
+const FAILURE_EXIT_CODE: i32 = 1;
+/// Exit code used when a check could not be run, distinct from the exit code
+/// of the errors of yoz itself.
+const CHECK_ERROR_EXIT_CODE: i32 = 3;
+
+/// Number of lines of the output shown for a failed check.
+const TAIL_LINES: usize = 20;
+/// Number of runs whose logs are kept.
+const KEPT_RUNS: usize = 10;
+/// Interval at which the running checks look for a cancellation.
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(50);
 
 /// Run multiples checks on your project.
+///
+/// The checks that do not build the project run alongside the others. The
+/// checks that build the project wait for each other unless they use
+/// separate target directories.
+///
+/// Exits with the code 1 if a check failed, 2 if yoz itself failed, e.g. with
+/// an invalid config or an unknown check, and 3 if a check could not be run.
 #[derive(Debug, clap::Parser)]
 pub struct Checks {
     /// Path of the project that will be checked.
@@ -25,19 +64,120 @@ pub struct Checks {
     /// Arguments given to the `cargo clippy` command.
     #[clap(long = "clippy")]
     clippy_args: Vec<String>,
+    /// Show the full output of a check of the last run instead of running
+    /// the checks.
+    ///
+    /// Available checks are: check, test, fmt, clippy and the checks of the
+    /// config file.
+    #[clap(long, value_name = "CHECK")]
+    last_log: Option<CheckKind>,
+    /// Build each check in its own target directory so they can all run at
+    /// the same time.
+    ///
+    /// The builds of the same check, e.g. with `--per-package`, still run one
+    /// after the other.
+    ///
+    /// The target directories are created in `target/yoz`.
+    #[clap(long)]
+    separate_target_dirs: bool,
+    /// Show the durations of the last runs of the checks of the project
+    /// instead of running the checks.
+    ///
+    /// Checks that became significantly slower than their recent median are
+    /// flagged.
+    #[clap(long, conflicts_with = "last-log")]
+    history: bool,
+    /// Write a report of the checks in the given format.
+    ///
+    /// Available formats are: json, junit.
+    #[clap(long, value_name = "FORMAT", requires = "output")]
+    report: Option<ReportFormat>,
+    /// File where the report is written.
+    #[clap(long, requires = "report")]
+    output: Option<path::PathBuf>,
+    /// Number of lints and files listed in the summary of the diagnostics of
+    /// `cargo check` and `cargo clippy`, starting with the noisiest.
+    #[clap(long, value_name = "N", default_value_t = diagnostics::DEFAULT_TOP)]
+    top: usize,
+    /// Run the checks on each member of the workspace with `-p <name>` and
+    /// show the results in a table.
+    ///
+    /// `--workspace` and `--all` are removed from the arguments of the
+    /// checks. The checks of the config file still run once.
+    #[clap(long)]
+    per_package: bool,
+    /// Run `cargo check` and `cargo clippy` on each member of the workspace
+    /// for every combination of its features.
+    ///
+    /// The maximum number of features of a combination and the features
+    /// that are never enabled can be configured in the `features` table of
+    /// the config file.
+    #[clap(long, conflicts_with = "each-feature")]
+    features_powerset: bool,
+    /// Run `cargo check` and `cargo clippy` on each member of the workspace
+    /// without its default features, then with each of its features.
+    #[clap(long)]
+    each_feature: bool,
+    /// Maximum number of features of the combinations checked with
+    /// `--features-powerset`.
+    #[clap(long, value_name = "N")]
+    depth: Option<usize>,
+    /// Run the checks again when the files of the project change.
+    ///
+    /// A run in progress is cancelled when new changes arrive. Hidden files,
+    /// `target` directories and the files ignored by git are not watched.
+    #[clap(long)]
+    watch: bool,
+    /// Run only the given checks, separated by commas.
+    ///
+    /// Available checks are: check, test, fmt, clippy and the checks of the
+    /// config file.
+    #[clap(long, value_name = "CHECKS", use_value_delimiter = true)]
+    only: Vec<CheckKind>,
+    /// Do not run the given checks, separated by commas.
+    #[clap(long, value_name = "CHECKS", use_value_delimiter = true)]
+    skip: Vec<CheckKind>,
+    /// Do not start the checks that are still queued once a check failed.
+    ///
+    /// The checks that are already running are not interrupted.
+    #[clap(long)]
+    fail_fast: bool,
+}
+
+/// Result of a run of the checks.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Outcome {
+    Success,
+    Failure,
+    Error,
+    Cancelled,
 }
 
 impl Checks {
+    /// Path of the project given to the command.
+    pub fn path(&self) -> Option<&path::Path> {
+        self.path.as_deref()
+    }
+
     pub fn run(
-        self,
+        mut self,
         default_check_args: Vec<String>,
         default_test_args: Vec<String>,
         default_fmt_args: Vec<String>,
         default_clippy_args: Vec<String>,
+        custom_checks: Vec<CustomCheck>,
+        features_config: Option<FeaturesConfig>,
     ) -> Result<()> {
-        let working_dir = set_working_dir(self.path)?;
+        if let Some(kind) = self.last_log {
+            return show_last_log(kind);
+        }
 
-        let start = std::time::Instant::now();
+        let working_dir = set_working_dir(self.path.take())?;
+
+        if self.history {
+            History::load()?.show(&working_dir);
+            return Ok(());
+        }
 
         if self.clean {
             if process::Command::new("cargo")
@@ -53,38 +193,377 @@ impl Checks {
             }
         }
 
-        let check_args = values_or_default(self.check_args, default_check_args, "checks_args")?;
-        let test_args = values_or_default(self.test_args, default_test_args, "test_args")?;
-        let fmt_args = values_or_default(self.fmt_args, default_fmt_args, "fmt_args")?;
-        let clippy_args = values_or_default(self.clippy_args, default_clippy_args, "clippy_args")?;
+        self.check_args = values_or_default(
+            mem::take(&mut self.check_args),
+            default_check_args,
+            "checks_args",
+        )?;
+        self.test_args = values_or_default(
+            mem::take(&mut self.test_args),
+            default_test_args,
+            "test_args",
+        )?;
+        self.fmt_args =
+            values_or_default(mem::take(&mut self.fmt_args), default_fmt_args, "fmt_args")?;
+        self.clippy_args = values_or_default(
+            mem::take(&mut self.clippy_args),
+            default_clippy_args,
+            "clippy_args",
+        )?;
 
-        let commands = vec![
-            ChecksCommand::check(&working_dir, check_args),
-            ChecksCommand::test(&working_dir, test_args),
-            ChecksCommand::fmt(&working_dir, fmt_args),
-            ChecksCommand::clippy(&working_dir, clippy_args),
-        ];
+        let custom_checks = if self.features_powerset || self.each_feature {
+            Vec::new()
+        } else {
+            custom_checks
+        };
 
-        let failed_commands = commands
-            .into_iter()
-            .filter_map(|x| x.execute(start).ok()?)
-            .collect::<Vec<String>>();
+        if self.watch {
+            return self.watch(&working_dir, &custom_checks, features_config.as_ref());
+        }
+
+        let cancel = AtomicBool::new(false);
+        match self.run_once(
+            &working_dir,
+            &custom_checks,
+            features_config.as_ref(),
+            &cancel,
+        )? {
+            Outcome::Error => process::exit(CHECK_ERROR_EXIT_CODE),
+            Outcome::Failure => process::exit(FAILURE_EXIT_CODE),
+            Outcome::Success | Outcome::Cancelled => Ok(()),
+        }
+    }
+
+    /// Run the checks each time the project changes, until interrupted.
+    fn watch(
+        &self,
+        working_dir: &path::Path,
+        custom_checks: &[CustomCheck],
+        features_config: Option<&FeaturesConfig>,
+    ) -> Result<()> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let watcher = Watcher::new(
+            working_dir,
+            self.output.iter().cloned().collect(),
+            cancel.clone(),
+        )?;
+
+        loop {
+            cancel.store(false, Ordering::SeqCst);
+
+            match self.run_once(working_dir, custom_checks, features_config, &cancel) {
+                Ok(Outcome::Cancelled) => {
+                    println!();
+                    println!("Changes detected, restarting the checks");
+                }
+                Ok(_) => {
+                    println!();
+                    println!("Watching for changes...");
+                    watcher.clear();
+                    watcher.wait()?;
+                }
+                Err(err) => {
+                    log::error!("{}", err);
+                    println!("Watching for changes...");
+                    watcher.clear();
+                    watcher.wait()?;
+                }
+            }
+
+            watcher.debounce();
+        }
+    }
+
+    /// Run the checks once and print their summary.
+    ///
+    /// The checks stop as soon as `cancel` is set.
+    fn run_once(
+        &self,
+        working_dir: &path::Path,
+        custom_checks: &[CustomCheck],
+        features_config: Option<&FeaturesConfig>,
+        cancel: &AtomicBool,
+    ) -> Result<Outcome> {
+        let check_args = &self.check_args;
+        let test_args = &self.test_args;
+        let fmt_args = &self.fmt_args;
+        let clippy_args = &self.clippy_args;
+
+        let mut targets = Vec::new();
+        let mut commands = Vec::new();
+
+        if self.features_powerset || self.each_feature {
+            let features_config = features_config.cloned().unwrap_or_default();
+            let depth = self.depth.or(features_config.depth);
+
+            for (package, features) in manifest::member_features(working_dir)? {
+                let features = features
+                    .into_iter()
+                    .filter(|x| !features_config.skip.contains(x))
+                    .collect::<Vec<String>>();
+
+                for combination in feature_combinations(&features, self.features_powerset, depth) {
+                    let target = if combination.is_empty() {
+                        format!("{} (no features)", package)
+                    } else {
+                        format!("{} ({})", package, combination.join(","))
+                    };
+
+                    for mut command in [
+                        ChecksCommand::check(
+                            working_dir,
+                            package_args(check_args, &package, Some(&combination)),
+                        ),
+                        ChecksCommand::clippy(
+                            working_dir,
+                            package_args(clippy_args, &package, Some(&combination)),
+                        ),
+                    ] {
+                        command.target = Some(target.clone());
+                        commands.push(command);
+                    }
+                    targets.push(target);
+                }
+            }
+        } else if self.per_package {
+            for package in manifest::member_names(working_dir)? {
+                for mut command in [
+                    ChecksCommand::check(working_dir, package_args(check_args, &package, None)),
+                    ChecksCommand::test(working_dir, package_args(test_args, &package, None)),
+                    ChecksCommand::fmt(working_dir, package_args(fmt_args, &package, None)),
+                    ChecksCommand::clippy(working_dir, package_args(clippy_args, &package, None)),
+                ] {
+                    command.target = Some(package.clone());
+                    commands.push(command);
+                }
+                targets.push(package);
+            }
+        } else {
+            commands.push(ChecksCommand::check(working_dir, check_args.clone()));
+            commands.push(ChecksCommand::test(working_dir, test_args.clone()));
+            commands.push(ChecksCommand::fmt(working_dir, fmt_args.clone()));
+            commands.push(ChecksCommand::clippy(working_dir, clippy_args.clone()));
+        }
+
+        for custom_check in custom_checks.iter().filter(|x| x.enabled) {
+            ensure!(
+                !commands
+                    .iter()
+                    .any(|x| x.kind.to_string() == custom_check.name.to_lowercase()),
+                "the name of the check `{}` is already used",
+                custom_check.name
+            );
+
+            commands.push(ChecksCommand::custom(working_dir, custom_check.clone()));
+        }
+
+        for kind in self.only.iter().chain(&self.skip) {
+            ensure!(
+                commands.iter().any(|x| &x.kind == kind),
+                "unknown check `{}`",
+                kind
+            );
+        }
+        commands.retain(|x| {
+            (self.only.is_empty() || self.only.contains(&x.kind)) && !self.skip.contains(&x.kind)
+        });
+        ensure!(!commands.is_empty(), "no checks to run");
+
+        let target_dir = if self.separate_target_dirs {
+            Some(
+                manifest::metadata(working_dir)?
+                    .target_directory
+                    .into_std_path_buf()
+                    .join("yoz"),
+            )
+        } else {
+            None
+        };
+
+        let multi_progress = MultiProgress::new();
+        let mut lanes = Vec::new();
+        // The builds sharing a target directory wait for each other on its
+        // lock, so they run in the same lane.
+        let mut build_lanes = BTreeMap::new();
+
+        for (index, mut command) in commands.into_iter().enumerate() {
+            let pb = multi_progress.add(create_pb());
+            pb.set_message(format!("{} (queued)", command.label()));
+
+            if !command.builds() {
+                lanes.push(vec![(index, command, pb)]);
+            } else {
+                let command_target_dir = target_dir
+                    .as_ref()
+                    .map(|target_dir| target_dir.join(command.kind.to_string()));
+                if let Some(command_target_dir) = &command_target_dir {
+                    command.command.env("CARGO_TARGET_DIR", command_target_dir);
+                }
+
+                build_lanes
+                    .entry(command_target_dir)
+                    .or_insert_with(Vec::new)
+                    .push((index, command, pb));
+            }
+        }
+        lanes.extend(build_lanes.into_values());
+
+        let failed = AtomicBool::new(false);
+        let skipped = Mutex::new(Vec::new());
+
+        let mut results = thread::scope(|scope| {
+            let handles = lanes
+                .into_iter()
+                .map(|lane| {
+                    let failed = &failed;
+                    let skipped = &skipped;
+
+                    scope.spawn(move || {
+                        let mut results = Vec::new();
+
+                        for (index, command, pb) in lane {
+                            if self.fail_fast && failed.load(Ordering::SeqCst) {
+                                pb.finish_with_message(format!("{} (skipped)", command.label()));
+                                if let Ok(mut skipped) = skipped.lock() {
+                                    skipped.push((index, command.label()));
+                                }
+                                continue;
+                            }
 
-        if !failed_commands.is_empty() {
+                            let result = command.execute(&pb, cancel);
+                            if !matches!(&result, Ok(output) if output.success) {
+                                failed.store(true, Ordering::SeqCst);
+                            }
+                            results.push((index, result));
+                        }
+
+                        results
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            multi_progress.join()?;
+
+            let mut results = Vec::new();
+            for handle in handles {
+                results.extend(
+                    handle
+                        .join()
+                        .map_err(|_| anyhow!("a check panicked while running"))?,
+                );
+            }
+
+            Ok::<_, anyhow::Error>(results)
+        })?;
+        results.sort_by_key(|(index, _)| *index);
+
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(Outcome::Cancelled);
+        }
+
+        let mut outputs = Vec::new();
+        let mut errored_commands = Vec::new();
+
+        for (_, result) in results {
+            match result {
+                Ok(output) => outputs.push(output),
+                Err(error) => errored_commands.push(error),
+            }
+        }
+
+        if let Err(err) = record_durations(working_dir, &outputs) {
+            log::error!("cannot update the history: {}", err);
+        }
+
+        let log_dir = match write_logs(&outputs) {
+            Ok(log_dir) => Some(log_dir),
+            Err(err) => {
+                log::error!("cannot write the logs: {}", err);
+                None
+            }
+        };
+
+        if let (Some(format), Some(output_path)) = (self.report, &self.output) {
+            let name = working_dir
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            report::write(format, output_path, &name, &outputs, &errored_commands)?;
+            log::info!("Report written to {}", output_path.display());
+        }
+
+        if !targets.is_empty() {
             println!();
-            println!("Fails ({}):", failed_commands.len());
-            for command in failed_commands {
-                println!("{}", command);
+            print_matrix(&targets, &outputs, &errored_commands);
+        }
+
+        println!();
+        for output in outputs.iter().filter(|x| x.kind.emits_diagnostics()) {
+            if output.target.is_none() || !output.diagnostics.is_empty() {
+                diagnostics::print_summary(&output.label(), &output.diagnostics, self.top);
             }
         }
 
-        Ok(())
+        let failed_outputs = outputs
+            .iter()
+            .filter(|output| !output.success)
+            .collect::<Vec<&CheckOutput>>();
+
+        if !failed_outputs.is_empty() {
+            println!();
+            println!("Fails ({}):", failed_outputs.len());
+            for output in &failed_outputs {
+                println!("{}", output.command_string);
+            }
+
+            for output in &failed_outputs {
+                println!();
+                println!("{} (last {} lines):", output.label(), TAIL_LINES);
+                println!("{}", output.tail(TAIL_LINES));
+            }
+
+            if let Some(log_dir) = log_dir {
+                println!();
+                println!("Full logs: {}", log_dir.display());
+            }
+        }
+
+        let mut skipped = skipped.into_inner().unwrap_or_default();
+        if !skipped.is_empty() {
+            skipped.sort();
+
+            println!();
+            println!("Skipped after the first failure ({}):", skipped.len());
+            for (_, label) in &skipped {
+                println!("{}", label);
+            }
+        }
+
+        if !errored_commands.is_empty() {
+            println!();
+            println!("Errors ({}):", errored_commands.len());
+            for command in &errored_commands {
+                println!("{} ({})", command.command_string, command.error);
+            }
+
+            return Ok(Outcome::Error);
+        }
+
+        if !failed_outputs.is_empty() {
+            return Ok(Outcome::Failure);
+        }
+
+        Ok(Outcome::Success)
     }
 }
 
 #[derive(Debug)]
 struct ChecksCommand {
     kind: CheckKind,
+    /// Package checked by the command, followed by the enabled features
+    /// when checking the feature combinations.
+    target: Option<String>,
     command: process::Command,
     command_string: String,
 }
@@ -95,6 +574,9 @@ impl ChecksCommand {
 
         let mut command = process::Command::new("cargo");
         command.current_dir(working_dir).arg("check");
+        if !args.iter().any(|arg| arg.starts_with("--message-format")) {
+            command.arg("--message-format=json");
+        }
 
         for arg in args {
             command_string.push(' ');
@@ -105,6 +587,7 @@ impl ChecksCommand {
 
         Self {
             kind: CheckKind::Check,
+            target: None,
             command,
             command_string,
         }
@@ -125,6 +608,7 @@ impl ChecksCommand {
 
         Self {
             kind: CheckKind::Test,
+            target: None,
             command,
             command_string,
         }
@@ -145,6 +629,7 @@ impl ChecksCommand {
 
         Self {
             kind: CheckKind::Fmt,
+            target: None,
             command,
             command_string,
         }
@@ -155,6 +640,9 @@ impl ChecksCommand {
 
         let mut command = process::Command::new("cargo");
         command.current_dir(working_dir).arg("clippy");
+        if !args.iter().any(|arg| arg.starts_with("--message-format")) {
+            command.arg("--message-format=json");
+        }
 
         for arg in args {
             command_string.push(' ');
@@ -165,59 +653,517 @@ impl ChecksCommand {
 
         Self {
             kind: CheckKind::Clippy,
+            target: None,
+            command,
+            command_string,
+        }
+    }
+
+    fn custom(working_dir: &path::Path, custom_check: CustomCheck) -> Self {
+        let mut command_string = custom_check.program.clone();
+
+        let mut command = process::Command::new(&custom_check.program);
+        command
+            .current_dir(match &custom_check.working_dir {
+                Some(dir) => working_dir.join(dir),
+                None => working_dir.to_path_buf(),
+            })
+            .envs(&custom_check.env);
+
+        for arg in custom_check.args {
+            command_string.push(' ');
+            command_string.push_str(&arg);
+
+            command.arg(arg);
+        }
+
+        Self {
+            kind: CheckKind::Custom(custom_check.name.to_lowercase()),
+            target: None,
             command,
             command_string,
         }
     }
 
-    fn execute(mut self, start: time::Instant) -> Result<Option<String>> {
-        let pb = create_pb();
+    /// Whether the check builds the project, taking the lock of the target
+    /// directory.
+    ///
+    /// The custom checks are considered to build the project when they run
+    /// `cargo`.
+    fn builds(&self) -> bool {
+        match &self.kind {
+            CheckKind::Fmt => false,
+            CheckKind::Custom(_) => self.command.get_program() == "cargo",
+            _ => true,
+        }
+    }
+
+    fn label(&self) -> String {
+        label(&self.kind, self.target.as_deref())
+    }
+
+    /// Run the check, killing it if `cancel` is set.
+    fn execute(mut self, pb: &ProgressBar, cancel: &AtomicBool) -> Result<CheckOutput, CheckError> {
+        let start = time::Instant::now();
+        if cancel.load(Ordering::SeqCst) {
+            return Err(self.fail(pb, start, "cancelled".to_string()));
+        }
 
         match &self.kind {
             CheckKind::Check => pb.set_message("Checking package..."),
             CheckKind::Test => pb.set_message("Testing..."),
             CheckKind::Fmt => pb.set_message("Checking formatting..."),
             CheckKind::Clippy => pb.set_message("Checking lints..."),
+            CheckKind::Custom(name) => pb.set_message(format!("Running {}...", name)),
         }
 
-        let res = self.command.output()?.status.success();
+        let mut child = match self
+            .command
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => return Err(self.fail(pb, start, err.to_string())),
+        };
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if cancel.load(Ordering::SeqCst) => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+
+                    return Err(self.fail(pb, start, "cancelled".to_string()));
+                }
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(err) => return Err(self.fail(pb, start, err.to_string())),
+            }
+        };
+        let output = process::Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        };
+
+        let duration = start.elapsed();
+        let res = output.status.success();
 
         pb.inc(1);
 
         pb.set_style(generate_style(res));
-        pb.finish_with_message(self.kind.generate_msg(start));
+        pb.finish_with_message(generate_msg(&self.label(), duration));
 
-        if res {
-            Ok(None)
+        let (diagnostics, mut output_string) = if self.kind.emits_diagnostics() {
+            diagnostics::parse(&output.stdout)
         } else {
-            Ok(Some(self.command_string))
+            (
+                Vec::new(),
+                String::from_utf8_lossy(&output.stdout).to_string(),
+            )
+        };
+        output_string.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        Ok(CheckOutput {
+            kind: self.kind,
+            target: self.target,
+            command_string: self.command_string,
+            success: res,
+            duration,
+            output: output_string,
+            diagnostics,
+        })
+    }
+
+    fn fail(self, pb: &ProgressBar, start: time::Instant, error: String) -> CheckError {
+        pb.set_style(generate_style(false));
+        pb.finish_with_message(generate_msg(&self.label(), start.elapsed()));
+
+        CheckError {
+            kind: self.kind,
+            target: self.target,
+            command_string: self.command_string,
+            error,
+        }
+    }
+}
+
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
         }
+
+        buffer
+    })
+}
+
+#[derive(Debug)]
+pub struct CheckOutput {
+    pub kind: CheckKind,
+    pub target: Option<String>,
+    pub command_string: String,
+    pub success: bool,
+    pub duration: time::Duration,
+    pub output: String,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Check defined in the config file, run after the builtin checks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomCheck {
+    pub name: String,
+    pub program: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Directory where the check is run, relative to the project.
+    pub working_dir: Option<path::PathBuf>,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Limits of the combinations checked with `--features-powerset` and
+/// `--each-feature`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FeaturesConfig {
+    /// Maximum number of features of a combination.
+    pub depth: Option<usize>,
+    /// Features never enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip: Vec<String>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+/// A check that could not be run.
+#[derive(Debug)]
+pub struct CheckError {
+    pub kind: CheckKind,
+    pub target: Option<String>,
+    pub command_string: String,
+    pub error: String,
+}
+
+impl CheckError {
+    pub fn label(&self) -> String {
+        label(&self.kind, self.target.as_deref())
+    }
+}
+
+impl CheckOutput {
+    pub fn label(&self) -> String {
+        label(&self.kind, self.target.as_deref())
+    }
+
+    fn tail(&self, lines: usize) -> String {
+        let all_lines = self.output.trim_end().lines().collect::<Vec<&str>>();
+
+        all_lines[all_lines.len().saturating_sub(lines)..].join("\n")
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum CheckKind {
     Check,
     Test,
     Fmt,
     Clippy,
+    Custom(String),
 }
 
 impl CheckKind {
-    fn generate_msg(self, start: time::Instant) -> String {
-        let mut message = String::new();
+    /// Whether the check is run with `--message-format=json` to collect its
+    /// diagnostics.
+    fn emits_diagnostics(&self) -> bool {
+        matches!(self, CheckKind::Check | CheckKind::Clippy)
+    }
+}
 
-        match self {
-            CheckKind::Check => message.push_str("check  "),
-            CheckKind::Test => message.push_str("test   "),
-            CheckKind::Fmt => message.push_str("fmt    "),
-            CheckKind::Clippy => message.push_str("clippy "),
+impl FromStr for CheckKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+
+        let kind = match s.as_str() {
+            "check" => Self::Check,
+            "test" => Self::Test,
+            "fmt" => Self::Fmt,
+            "clippy" => Self::Clippy,
+            "" => bail!("Cannot parse check from an empty string"),
+            _ => Self::Custom(s),
         };
 
-        message.push_str(format!("({}s)", start.elapsed().as_secs()).as_str());
+        Ok(kind)
+    }
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Check => write!(f, "check"),
+            Self::Test => write!(f, "test"),
+            Self::Fmt => write!(f, "fmt"),
+            Self::Clippy => write!(f, "clippy"),
+            Self::Custom(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Name of the check shown to the user, prefixed by the package it checks.
+fn label(kind: &CheckKind, target: Option<&str>) -> String {
+    match target {
+        Some(target) => format!("{}/{}", target, kind),
+        None => kind.to_string(),
+    }
+}
+
+fn generate_msg(label: &str, duration: time::Duration) -> String {
+    format!("{:<6} ({:.1}s)", label, duration.as_secs_f64())
+}
+
+/// Remove the arguments selecting the whole workspace, placed before `--`,
+/// and select the package instead.
+///
+/// When checking a combination of features, the arguments selecting the
+/// features are replaced as well.
+fn package_args(args: &[String], package: &str, features: Option<&[String]>) -> Vec<String> {
+    let separator = args.iter().position(|x| x == "--").unwrap_or(args.len());
+
+    let mut package_args = vec!["-p".to_string(), package.to_string()];
+    let mut args_iter = args[..separator].iter();
+    while let Some(arg) = args_iter.next() {
+        if arg == "--workspace" || arg == "--all" {
+            continue;
+        }
 
-        message
+        if features.is_some() {
+            if arg == "--features" || arg == "-F" {
+                args_iter.next();
+                continue;
+            }
+            if arg == "--all-features"
+                || arg == "--no-default-features"
+                || arg.starts_with("--features=")
+                || arg.starts_with("-F")
+            {
+                continue;
+            }
+        }
+
+        package_args.push(arg.clone());
     }
+
+    if let Some(features) = features {
+        package_args.push("--no-default-features".to_string());
+        if !features.is_empty() {
+            package_args.push("--features".to_string());
+            package_args.push(features.join(","));
+        }
+    }
+
+    package_args.extend(args[separator..].iter().cloned());
+
+    package_args
+}
+
+/// Get the combinations of features to check, starting with no features.
+///
+/// Returns every combination of at most `depth` features with `powerset`,
+/// otherwise each feature alone.
+fn feature_combinations(
+    features: &[String],
+    powerset: bool,
+    depth: Option<usize>,
+) -> Vec<Vec<String>> {
+    if !powerset {
+        let mut combinations = vec![Vec::new()];
+        combinations.extend(features.iter().map(|x| vec![x.clone()]));
+
+        return combinations;
+    }
+
+    let depth = depth.unwrap_or(features.len());
+    let mut combinations = vec![Vec::new()];
+
+    for size in 1..=depth.min(features.len()) {
+        let mut indices = (0..size).collect::<Vec<usize>>();
+
+        loop {
+            combinations.push(indices.iter().map(|i| features[*i].clone()).collect());
+
+            // Move to the next combination in lexicographic order.
+            let mut i = size;
+            while i > 0 && indices[i - 1] == features.len() - size + i - 1 {
+                i -= 1;
+            }
+            if i == 0 {
+                break;
+            }
+            indices[i - 1] += 1;
+            for j in i..size {
+                indices[j] = indices[j - 1] + 1;
+            }
+        }
+    }
+
+    combinations
+}
+
+/// Print the result of every check of every package.
+fn print_matrix(targets: &[String], outputs: &[CheckOutput], errors: &[CheckError]) {
+    let kinds = [
+        CheckKind::Check,
+        CheckKind::Test,
+        CheckKind::Fmt,
+        CheckKind::Clippy,
+    ]
+    .into_iter()
+    .filter(|kind| {
+        outputs.iter().any(|x| &x.kind == kind) || errors.iter().any(|x| &x.kind == kind)
+    })
+    .collect::<Vec<CheckKind>>();
+    let width = targets.iter().map(|x| x.len()).max().unwrap_or_default();
+
+    let mut header = format!("{:<width$}", "", width = width);
+    for kind in &kinds {
+        header.push_str(&format!("  {:<6}", kind.to_string()));
+    }
+    println!("{}", header.trim_end());
+
+    for target in targets {
+        let mut line = format!("{:<width$}", target, width = width);
+
+        for kind in &kinds {
+            let is = |x_kind: &CheckKind, x_target: &Option<String>| {
+                x_kind == kind && x_target.as_deref() == Some(target.as_str())
+            };
+
+            let result = if let Some(output) = outputs.iter().find(|x| is(&x.kind, &x.target)) {
+                if output.success {
+                    "ok"
+                } else {
+                    "FAIL"
+                }
+            } else if errors.iter().any(|x| is(&x.kind, &x.target)) {
+                "error"
+            } else {
+                "-"
+            };
+
+            line.push_str(&format!("  {:<6}", result));
+        }
+
+        println!("{}", line.trim_end());
+    }
+}
+
+fn record_durations(working_dir: &path::Path, outputs: &[CheckOutput]) -> Result<()> {
+    let mut history = History::load()?;
+
+    for output in outputs {
+        history.record(
+            working_dir,
+            &output.label(),
+            output.success,
+            output.duration,
+        );
+    }
+
+    history.save()
+}
+
+/// Write the output of the checks in a new directory of the XDG cache.
+///
+/// Only the logs of the last runs are kept.
+fn write_logs(outputs: &[CheckOutput]) -> Result<path::PathBuf> {
+    let logs_dir = xdg::BaseDirectories::with_prefix("yoz")?.create_cache_directory("checks")?;
+
+    let mut runs = run_dirs(&logs_dir)?;
+    while runs.len() >= KEPT_RUNS {
+        fs::remove_dir_all(runs.remove(0))?;
+    }
+
+    let run_dir = logs_dir.join(chrono::Local::now().format("%Y%m%d-%H%M%S%.3f").to_string());
+    fs::create_dir_all(&run_dir)?;
+
+    for output in outputs {
+        let log_path = run_dir.join(format!("{}.log", output.label()));
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(
+            log_path,
+            format!("$ {}\n\n{}", output.command_string, output.output),
+        )?;
+    }
+
+    Ok(run_dir)
+}
+
+fn run_dirs(logs_dir: &path::Path) -> Result<Vec<path::PathBuf>> {
+    let mut runs = fs::read_dir(logs_dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<path::PathBuf>>();
+    runs.sort();
+
+    Ok(runs)
+}
+
+/// Show the log of the check in the last run, or the logs of the check for
+/// every package if the last run used `--per-package`.
+fn show_last_log(kind: CheckKind) -> Result<()> {
+    let logs_dir = xdg::BaseDirectories::with_prefix("yoz")?.create_cache_directory("checks")?;
+
+    let run_dir = match run_dirs(&logs_dir)?.pop() {
+        Some(run_dir) => run_dir,
+        None => bail!("no checks have been run yet"),
+    };
+
+    let file_name = format!("{}.log", kind);
+    let mut log_paths = vec![run_dir.join(&file_name)];
+    if !log_paths[0].exists() {
+        log_paths = run_dirs(&run_dir)?
+            .into_iter()
+            .map(|package_dir| package_dir.join(&file_name))
+            .filter(|log_path| log_path.exists())
+            .collect();
+    }
+    ensure!(
+        !log_paths.is_empty(),
+        "`{}` did not run during the last run",
+        kind
+    );
+
+    match env::var("PAGER") {
+        Ok(pager) if !pager.is_empty() => {
+            ensure!(
+                process::Command::new(pager)
+                    .args(&log_paths)
+                    .status()?
+                    .success(),
+                "cannot open the logs in {}",
+                run_dir.display()
+            );
+        }
+        _ => {
+            for log_path in log_paths {
+                print!("{}", fs::read_to_string(&log_path)?);
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn create_pb() -> ProgressBar {
@@ -242,3 +1188,107 @@ fn generate_style(is_success: bool) -> ProgressStyle {
             .progress_chars("#>-")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn feature_combinations_without_features() {
+        assert_eq!(
+            feature_combinations(&[], true, None),
+            vec![Vec::<String>::new()]
+        );
+        assert_eq!(
+            feature_combinations(&[], false, None),
+            vec![Vec::<String>::new()]
+        );
+    }
+
+    #[test]
+    fn feature_combinations_depth() {
+        let features = strings(&["a", "b", "c"]);
+
+        assert_eq!(
+            feature_combinations(&features, true, Some(0)),
+            vec![strings(&[])]
+        );
+        assert_eq!(
+            feature_combinations(&features, true, Some(1)),
+            vec![
+                strings(&[]),
+                strings(&["a"]),
+                strings(&["b"]),
+                strings(&["c"])
+            ]
+        );
+        assert_eq!(
+            feature_combinations(&features, true, None),
+            vec![
+                strings(&[]),
+                strings(&["a"]),
+                strings(&["b"]),
+                strings(&["c"]),
+                strings(&["a", "b"]),
+                strings(&["a", "c"]),
+                strings(&["b", "c"]),
+                strings(&["a", "b", "c"]),
+            ]
+        );
+        assert_eq!(
+            feature_combinations(&features, true, Some(3)),
+            feature_combinations(&features, true, Some(10))
+        );
+    }
+
+    #[test]
+    fn feature_combinations_each_feature() {
+        assert_eq!(
+            feature_combinations(&strings(&["a", "b"]), false, Some(2)),
+            vec![strings(&[]), strings(&["a"]), strings(&["b"])]
+        );
+    }
+
+    #[test]
+    fn package_args_without_features() {
+        assert_eq!(
+            package_args(
+                &strings(&["--workspace", "--features", "x", "--", "--all"]),
+                "a",
+                None
+            ),
+            strings(&["-p", "a", "--features", "x", "--", "--all"])
+        );
+    }
+
+    #[test]
+    fn package_args_with_features() {
+        assert_eq!(
+            package_args(
+                &strings(&["--all", "--features", "x", "-F", "y", "-Fz", "--tests"]),
+                "a",
+                Some(&strings(&["b", "c"]))
+            ),
+            strings(&[
+                "-p",
+                "a",
+                "--tests",
+                "--no-default-features",
+                "--features",
+                "b,c"
+            ])
+        );
+        assert_eq!(
+            package_args(
+                &strings(&["--all-features", "--features=x", "--", "-D", "warnings"]),
+                "a",
+                Some(&[])
+            ),
+            strings(&["-p", "a", "--no-default-features", "--", "-D", "warnings"])
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    changes::Changes,
+    manifest,
+    template::{self, Variables},
+};
+use anyhow::{bail, Result};
+use std::path::Path;
+
+pub fn add_ci(
+    changes: &mut Changes,
+    project_dir_path: &Path,
+    variables: &Variables,
+    lib: bool,
+    no_windows: bool,
+    no_osx: bool,
+) -> Result<()> {
+    let workflows_dir_path = project_dir_path.join(".github").join("workflows");
+
+    let mut os = vec!["ubuntu-latest"];
+    if !no_windows {
+        os.push("windows-latest");
+    }
+    if !no_osx {
+        os.push("macos-latest");
+    }
+    let variables = variables.clone().with("os", os.join(", "));
+
+    let (pr, main) = if lib {
+        ("ci/pr-lib.yml", "ci/main-lib.yml")
+    } else {
+        ("ci/pr.yml", "ci/main.yml")
+    };
+
+    changes.create(
+        workflows_dir_path.join("pr.yml"),
+        template::render(pr, &variables)?,
+    );
+    changes.create(
+        workflows_dir_path.join("main.yml"),
+        template::render(main, &variables)?,
+    );
+
+    if lib {
+        changes.create(
+            workflows_dir_path.join("release.yml"),
+            template::render("ci/publish.yml", &variables)?,
+        );
+    }
+
+    Ok(())
+}
+
+/// Find the name of the binary built by the release workflow of the package.
+pub fn find_bin(package_dir_path: &Path) -> Result<String> {
+    let metadata = manifest::metadata(package_dir_path)?;
+    let package = manifest::package(&metadata, package_dir_path)?;
+
+    match package
+        .targets
+        .iter()
+        .find(|target| target.kind.iter().any(|kind| kind == "bin"))
+    {
+        Some(target) => Ok(target.name.clone()),
+        None => bail!("`{}` does not contain a binary", package.name),
+    }
+}
+
+/// Add a release workflow for the binary `bin`.
+pub fn add_release(
+    changes: &mut Changes,
+    project_dir_path: &Path,
+    bin: &str,
+    variables: &Variables,
+    targets: Vec<String>,
+) -> Result<()> {
+    let mut matrix = String::new();
+    for target in targets {
+        let (os, ext) = if target.contains("windows") {
+            ("windows-latest", ".exe")
+        } else if target.contains("apple") {
+            ("macos-latest", "")
+        } else {
+            ("ubuntu-latest", "")
+        };
+
+        matrix.push_str(&format!(
+            "          - target: {}\n            os: {}\n            ext: \"{}\"\n",
+            target, os, ext
+        ));
+    }
+    matrix.pop();
+
+    let workflows_dir_path = project_dir_path.join(".github").join("workflows");
+
+    changes.create(
+        workflows_dir_path.join("release.yml"),
+        template::render(
+            "ci/release.yml",
+            &variables.clone().with("bin", bin).with("matrix", matrix),
+        )?,
+    );
+
+    Ok(())
+}
@@ -1,7 +1,49 @@
-use crate::{background::Position, screen::Monitor};
-use anyhow::Result;
+use crate::{
+    background::Position,
+    checks::{CustomCheck, FeaturesConfig},
+    new::Preset,
+    screen::Monitor,
+};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Value};
+
+/// Name of the config file of a project.
+const PROJECT_CONFIG_FILE: &str = ".yoz.toml";
+
+/// Create the config file from the author's config.
+#[derive(Debug, clap::Parser)]
+pub struct ConfigCommand {
+    /// Print the config used in the given directory and where each value
+    /// comes from instead of creating the config file.
+    #[clap(long)]
+    show_effective: bool,
+    /// Directory of the project whose config is shown, defaults to the
+    /// current directory.
+    #[clap(requires = "show-effective")]
+    path: Option<PathBuf>,
+}
+
+impl ConfigCommand {
+    /// Path of the project given to the command.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn run(self, project_dir: &Path) -> Result<()> {
+        if self.show_effective {
+            Layers::load(project_dir)?.show();
+            Ok(())
+        } else {
+            Config::create_from_dot()
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
@@ -15,6 +57,12 @@ pub struct Config {
     pub default_clippy_args: Vec<String>,
     #[serde(rename = "full_name")]
     pub default_full_name: Option<String>,
+    #[serde(
+        default,
+        rename = "release_targets",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub default_release_targets: Vec<String>,
     #[serde(rename = "background_file_path")]
     pub default_bg_file_path: Option<PathBuf>,
     #[serde(rename = "background_position")]
@@ -38,6 +86,11 @@ pub struct Config {
     pub default_net_device: Option<String>,
     pub main_monitor: Option<Monitor>,
     pub external_monitor: Option<Monitor>,
+    pub features: Option<FeaturesConfig>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, Preset>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<CustomCheck>,
 }
 
 impl Config {
@@ -48,6 +101,9 @@ impl Config {
             default_fmt_args: Vec::new(),
             default_clippy_args: Vec::new(),
             default_full_name: None,
+            default_release_targets: Vec::new(),
+            presets: BTreeMap::new(),
+            checks: Vec::new(),
             default_bg_file_path: None,
             default_bg_position: None,
             aur_dir: None,
@@ -62,23 +118,18 @@ impl Config {
             default_net_device: None,
             main_monitor: None,
             external_monitor: None,
+            features: None,
         }
     }
 
-    pub fn get_or_create() -> Result<Self> {
-        let config_file_path =
-            xdg::BaseDirectories::with_prefix("yoz")?.place_config_file("config.toml")?;
-
-        let config: Self = match fs::read(&config_file_path) {
-            Ok(file) => toml::de::from_slice(&file)?,
-            Err(_) => {
-                let config = Self::new();
-                fs::write(&config_file_path, toml::ser::to_string(&config)?)?;
-                println!("Config file created at: {}", config_file_path.display());
-
-                config
-            }
-        };
+    /// Get the global config, creating it if it does not exist, merged with
+    /// the config of the project at `project_dir`.
+    ///
+    /// The config of the project is read from the `.yoz.toml` or
+    /// `[package.metadata.yoz]` table of a `Cargo.toml` closest to
+    /// `project_dir`, and overrides the global config field by field.
+    pub fn get_or_create(project_dir: &Path) -> Result<Self> {
+        let config = Value::Table(Layers::load(project_dir)?.table).try_into()?;
 
         Ok(config)
     }
@@ -97,6 +148,29 @@ impl Config {
                 "warnings".to_string(),
             ],
             default_full_name: Some("Yohan Boogaert".to_string()),
+            default_release_targets: vec!["x86_64-unknown-linux-gnu".to_string()],
+            presets: BTreeMap::from([
+                ("default".to_string(), Preset::standard()),
+                (
+                    "lib".to_string(),
+                    Preset {
+                        headers: true,
+                        ..Preset::standard()
+                    },
+                ),
+            ]),
+            checks: vec![CustomCheck {
+                name: "doc".to_string(),
+                program: "cargo".to_string(),
+                args: vec![
+                    "doc".to_string(),
+                    "--workspace".to_string(),
+                    "--no-deps".to_string(),
+                ],
+                working_dir: None,
+                enabled: true,
+                env: BTreeMap::from([("RUSTDOCFLAGS".to_string(), "-D warnings".to_string())]),
+            }],
             default_bg_file_path: Some(PathBuf::from("/home/yozhgoor/Pictures/BG_1920_1080.png")),
             default_bg_position: Some(Position::Fill),
 
@@ -126,6 +200,10 @@ impl Config {
                 height: 1080,
                 rate: 144,
             }),
+            features: Some(FeaturesConfig {
+                depth: Some(2),
+                skip: Vec::new(),
+            }),
         }
     }
 
@@ -141,6 +219,165 @@ impl Config {
     }
 }
 
+/// The global config merged with the config of the project.
+struct Layers {
+    table: Table,
+    global: PathBuf,
+    project: Option<PathBuf>,
+    /// Files that set the values overridden by the config of the project,
+    /// by dotted key.
+    sources: BTreeMap<String, PathBuf>,
+}
+
+impl Layers {
+    fn load(project_dir: &Path) -> Result<Self> {
+        let global = xdg::BaseDirectories::with_prefix("yoz")?.place_config_file("config.toml")?;
+
+        let mut table = match fs::read_to_string(&global) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("cannot parse {}", global.display()))?,
+            Err(_) => {
+                let config = Config::new();
+                fs::write(&global, toml::ser::to_string(&config)?)?;
+                println!("Config file created at: {}", global.display());
+
+                Table::new()
+            }
+        };
+
+        let mut sources = BTreeMap::new();
+        let project = match find_project_config(&env::current_dir()?.join(project_dir))? {
+            Some((path, project_table)) => {
+                merge(&mut table, project_table, "", &path, &mut sources);
+                Some(path)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            table,
+            global,
+            project,
+            sources,
+        })
+    }
+
+    fn show(&self) {
+        println!("# global: {}", self.global.display());
+        match &self.project {
+            Some(project) => println!("# project: {}", project.display()),
+            None => println!("# project: none"),
+        }
+        println!();
+
+        self.show_table(&self.table, "");
+    }
+
+    fn show_table(&self, table: &Table, prefix: &str) {
+        for (key, value) in table {
+            let key = format!("{}{}", prefix, key);
+
+            match value {
+                Value::Table(table) => self.show_table(table, &format!("{}.", key)),
+                value => println!(
+                    "{} = {}  # {}",
+                    key,
+                    inline(value),
+                    self.source(&key).display()
+                ),
+            }
+        }
+    }
+
+    /// Find the file that set the value of the dotted key.
+    fn source(&self, key: &str) -> &Path {
+        let mut key = key;
+
+        loop {
+            if let Some(source) = self.sources.get(key) {
+                return source;
+            }
+
+            match key.rfind('.') {
+                Some(index) => key = &key[..index],
+                None => return &self.global,
+            }
+        }
+    }
+}
+
+/// Format the value on a single line.
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Table(table) => format!(
+            "{{ {} }}",
+            table
+                .iter()
+                .map(|(key, value)| format!("{} = {}", key, inline(value)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        Value::Array(array) => format!(
+            "[{}]",
+            array.iter().map(inline).collect::<Vec<String>>().join(", ")
+        ),
+        value => value.to_string(),
+    }
+}
+
+/// Find the closest config of a project in the directory or its parents.
+fn find_project_config(dir: &Path) -> Result<Option<(PathBuf, Table)>> {
+    for dir in dir.ancestors() {
+        let path = dir.join(PROJECT_CONFIG_FILE);
+        if path.exists() {
+            let table = toml::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("cannot parse {}", path.display()))?;
+
+            return Ok(Some((path, table)));
+        }
+
+        let path = dir.join("Cargo.toml");
+        if path.exists() {
+            let manifest: Value = toml::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("cannot parse {}", path.display()))?;
+
+            if let Some(Value::Table(table)) = manifest
+                .get("package")
+                .and_then(|x| x.get("metadata"))
+                .and_then(|x| x.get("yoz"))
+            {
+                return Ok(Some((path, table.clone())));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Merge the tables recursively, the values of `overlay` replacing the values
+/// of `base`.
+fn merge(
+    base: &mut Table,
+    overlay: Table,
+    prefix: &str,
+    source: &Path,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in overlay {
+        let dotted_key = format!("{}{}", prefix, key);
+
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => {
+                merge(base, overlay, &format!("{}.", dotted_key), source, sources)
+            }
+            (_, value) => {
+                sources.insert(dotted_key, source.to_path_buf());
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,112 @@
+use cargo_metadata::{diagnostic::DiagnosticLevel, Message};
+use std::{cmp::Reverse, collections::BTreeMap};
+
+/// Number of lints and files listed in the summary by default.
+pub const DEFAULT_TOP: usize = 10;
+
+/// An error or a warning emitted by the compiler or by clippy.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub is_error: bool,
+    pub lint: String,
+    pub file: String,
+    rendered: String,
+}
+
+/// Parse the JSON messages written by cargo with `--message-format=json`.
+///
+/// Returns the errors and the warnings found in the messages and the human
+/// readable output, made of the rendered diagnostics and the lines that are
+/// not JSON messages.
+pub fn parse(stdout: &[u8]) -> (Vec<Diagnostic>, String) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut output = String::new();
+
+    for message in Message::parse_stream(stdout).flatten() {
+        match message {
+            Message::CompilerMessage(message) => {
+                let message = message.message;
+                let rendered = message.rendered.unwrap_or_default();
+
+                let is_error = match message.level {
+                    DiagnosticLevel::Error | DiagnosticLevel::Ice => true,
+                    DiagnosticLevel::Warning => false,
+                    _ => continue,
+                };
+                let file = match message.spans.iter().find(|span| span.is_primary) {
+                    Some(span) => span.file_name.clone(),
+                    None => {
+                        output.push_str(&rendered);
+                        continue;
+                    }
+                };
+
+                let diagnostic = Diagnostic {
+                    is_error,
+                    lint: message
+                        .code
+                        .map(|code| code.code)
+                        .unwrap_or_else(|| "(no lint)".to_string()),
+                    file,
+                    rendered,
+                };
+
+                // The same diagnostic is emitted for every target that
+                // includes the file.
+                if !diagnostics.contains(&diagnostic) {
+                    output.push_str(&diagnostic.rendered);
+                    diagnostics.push(diagnostic);
+                }
+            }
+            Message::TextLine(line) => {
+                output.push_str(&line);
+                output.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    (diagnostics, output)
+}
+
+/// Print the number of errors and warnings, and the `top` lints and files
+/// that have the most of them.
+pub fn print_summary(name: &str, diagnostics: &[Diagnostic], top: usize) {
+    let errors = diagnostics.iter().filter(|x| x.is_error).count();
+    let warnings = diagnostics.len() - errors;
+
+    println!("{}: {} errors, {} warnings", name, errors, warnings);
+
+    if diagnostics.is_empty() {
+        return;
+    }
+
+    print_counts(
+        "lints",
+        diagnostics.iter().map(|x| x.lint.as_str()).collect(),
+        top,
+    );
+    print_counts(
+        "files",
+        diagnostics.iter().map(|x| x.file.as_str()).collect(),
+        top,
+    );
+}
+
+fn print_counts(title: &str, keys: Vec<&str>, top: usize) {
+    let mut counts = BTreeMap::new();
+    for key in keys {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+
+    let mut counts = counts.into_iter().collect::<Vec<(&str, usize)>>();
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+
+    println!("  {}:", title);
+    for (key, count) in counts.iter().take(top) {
+        println!("    {:>4} {}", count, key);
+    }
+    if counts.len() > top {
+        println!("    ... and {} more", counts.len() - top);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{changes::Changes, license::Copyright};
+use anyhow::Result;
+use chrono::Datelike;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+const SPDX_PREFIX: &str = "// SPDX-License-Identifier:";
+const COPYRIGHT_PREFIX: &str = "// Copyright";
+
+/// Insert or update the SPDX and copyright header of every Rust file of the
+/// project.
+///
+/// Hidden and `target` directories are skipped, as well as generated files.
+pub fn add_headers(
+    changes: &mut Changes,
+    project_dir_path: &Path,
+    full_name: &str,
+    expression: &str,
+) -> Result<()> {
+    let year = chrono::Local::now().date().year();
+
+    for entry in WalkDir::new(project_dir_path)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !is_skipped(entry))
+    {
+        let entry = entry?;
+
+        if !entry.file_type().is_file()
+            || entry.path().extension().and_then(|x| x.to_str()) != Some("rs")
+        {
+            continue;
+        }
+
+        let content = changes.read_to_string(entry.path())?;
+        if is_generated(&content) {
+            continue;
+        }
+
+        changes.write(
+            entry.path(),
+            update_header(&content, year, full_name, expression),
+        );
+    }
+
+    Ok(())
+}
+
+fn is_skipped(entry: &DirEntry) -> bool {
+    let name = entry.file_name().to_string_lossy();
+
+    entry.file_type().is_dir() && (name.starts_with('.') || name == "target" || name == "generated")
+}
+
+fn is_generated(content: &str) -> bool {
+    content
+        .lines()
+        .take(5)
+        .any(|line| line.contains("@generated"))
+}
+
+/// Replace the SPDX line and add the copyright of `full_name`, or extend its
+/// years to `year`.
+///
+/// The rest of the file is kept as is, the header uses the line endings of
+/// the file.
+fn update_header(content: &str, year: i32, full_name: &str, expression: &str) -> String {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut rest = content;
+    let mut copyrights = Vec::new();
+
+    while !rest.is_empty() {
+        let (line, next) = match rest.find('\n') {
+            Some(index) => rest.split_at(index + 1),
+            None => (rest, ""),
+        };
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        // The SPDX lines are replaced.
+        if line.starts_with(COPYRIGHT_PREFIX) {
+            copyrights.push(line.to_string());
+        } else if !line.starts_with(SPDX_PREFIX) {
+            break;
+        }
+
+        rest = next;
+    }
+
+    match copyrights.iter_mut().find(|line| line.contains(full_name)) {
+        Some(line) => {
+            if let Some(copyright) = line.strip_prefix("// ").and_then(Copyright::parse) {
+                *line = format!("// {}", copyright.extend(year, full_name));
+            }
+        }
+        None => copyrights.push(format!("{} (c) {} {}", COPYRIGHT_PREFIX, year, full_name)),
+    }
+
+    let mut header = format!("{} {}{}", SPDX_PREFIX, expression, newline);
+    for copyright in copyrights {
+        header.push_str(&copyright);
+        header.push_str(newline);
+    }
+
+    if !rest.is_empty() {
+        if !rest.starts_with(newline) {
+            header.push_str(newline);
+        }
+        header.push_str(rest);
+    }
+
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPRESSION: &str = "MIT OR Apache-2.0";
+
+    #[test]
+    fn add_header() {
+        assert_eq!(
+            update_header("fn main() {}\n", 2026, "Jane Doe", EXPRESSION),
+            "// SPDX-License-Identifier: MIT OR Apache-2.0\n\
+            // Copyright (c) 2026 Jane Doe\n\
+            \n\
+            fn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn second_run_is_a_no_op() {
+        for content in [
+            "fn main() {}\n",
+            "fn main() {}",
+            "",
+            "//! Docs\n\nfn main() {}\n",
+        ] {
+            let once = update_header(content, 2026, "Jane Doe", EXPRESSION);
+            let twice = update_header(&once, 2026, "Jane Doe", EXPRESSION);
+
+            assert_eq!(once, twice);
+        }
+    }
+
+    #[test]
+    fn keep_crlf() {
+        assert_eq!(
+            update_header("fn main() {\r\n}\r\n", 2026, "Jane Doe", EXPRESSION),
+            "// SPDX-License-Identifier: MIT OR Apache-2.0\r\n\
+            // Copyright (c) 2026 Jane Doe\r\n\
+            \r\n\
+            fn main() {\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn extend_copyright_year() {
+        assert_eq!(
+            update_header(
+                "// SPDX-License-Identifier: MIT\n\
+                // Copyright (c) 2021 John Doe\n\
+                // Copyright (c) 2021 Jane Doe <jane@example.com>\n\
+                \n\
+                fn main() {}\n",
+                2026,
+                "Jane Doe",
+                EXPRESSION
+            ),
+            "// SPDX-License-Identifier: MIT OR Apache-2.0\n\
+            // Copyright (c) 2021 John Doe\n\
+            // Copyright (c) 2021-2026 Jane Doe <jane@example.com>\n\
+            \n\
+            fn main() {}\n"
+        );
+    }
+}
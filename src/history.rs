@@ -0,0 +1,158 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path, time::Duration};
+
+/// Number of durations kept for each check of a project.
+const KEPT_RECORDS: usize = 50;
+/// Number of previous runs used to compute the median duration of a check.
+const MEDIAN_RUNS: usize = 10;
+/// A check is flagged when its last duration exceeds its median by this
+/// factor.
+const SLOWER_FACTOR: f64 = 1.5;
+/// Minimum slowdown in seconds for a check to be flagged, ignoring the noise
+/// of the fast checks.
+const MIN_SLOWDOWN: f64 = 1.0;
+
+/// Durations of the checks of every project, stored in the XDG data
+/// directory of yoz.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default)]
+    projects: BTreeMap<String, Vec<Record>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    date: String,
+    check: String,
+    success: bool,
+    duration: f64,
+}
+
+impl History {
+    pub fn load() -> Result<Self> {
+        let history_file_path =
+            xdg::BaseDirectories::with_prefix("yoz")?.place_data_file("checks-history.toml")?;
+
+        let history = match fs::read(&history_file_path) {
+            Ok(file) => toml::de::from_slice(&file)?,
+            Err(_) => Self::default(),
+        };
+
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let history_file_path =
+            xdg::BaseDirectories::with_prefix("yoz")?.place_data_file("checks-history.toml")?;
+
+        fs::write(history_file_path, toml::ser::to_string(self)?)?;
+
+        Ok(())
+    }
+
+    /// Add the duration of a check of the project.
+    pub fn record(&mut self, project_dir: &Path, check: &str, success: bool, duration: Duration) {
+        let records = self.projects.entry(project_key(project_dir)).or_default();
+
+        records.push(Record {
+            date: chrono::Local::now().to_rfc3339(),
+            check: check.to_string(),
+            success,
+            duration: duration.as_secs_f64(),
+        });
+
+        let count = records.iter().filter(|x| x.check == check).count();
+        if count > KEPT_RECORDS {
+            if let Some(index) = records.iter().position(|x| x.check == check) {
+                records.remove(index);
+            }
+        }
+    }
+
+    /// Print the recent durations of the checks of the project.
+    ///
+    /// A check is flagged when its last successful run is significantly
+    /// slower than the median of the previous ones.
+    pub fn show(&self, project_dir: &Path) {
+        let records = match self.projects.get(&project_key(project_dir)) {
+            Some(records) => records,
+            None => {
+                println!("No history for {}", project_dir.display());
+                return;
+            }
+        };
+
+        let mut checks = records
+            .iter()
+            .map(|x| x.check.as_str())
+            .collect::<Vec<&str>>();
+        checks.sort_unstable();
+        checks.dedup();
+
+        for check in checks {
+            let durations = records
+                .iter()
+                .filter(|x| x.check == check && x.success)
+                .map(|x| x.duration)
+                .collect::<Vec<f64>>();
+            let runs = records.iter().filter(|x| x.check == check).count();
+
+            let (last, previous) = match durations.split_last() {
+                Some(split) => split,
+                None => {
+                    println!("{:<7} no successful run ({} runs)", check, runs);
+                    continue;
+                }
+            };
+            let previous = &previous[previous.len().saturating_sub(MEDIAN_RUNS)..];
+
+            let mut line = format!("{:<7} last {:>6.1}s", check, last);
+            match median(previous) {
+                Some(median) => {
+                    line.push_str(&format!("  median {:>6.1}s", median));
+                    if *last > median * SLOWER_FACTOR && last - median > MIN_SLOWDOWN {
+                        line.push_str(&format!(
+                            "  slower than usual (+{:.0}%)",
+                            (last / median - 1.0) * 100.0
+                        ));
+                    }
+                }
+                None => line.push_str("  median      -"),
+            }
+            line.push_str(&format!("  ({} runs)", runs));
+
+            let trend = durations[durations.len().saturating_sub(MEDIAN_RUNS)..]
+                .iter()
+                .map(|x| format!("{:.1}", x))
+                .collect::<Vec<String>>()
+                .join(" ");
+            println!("{}", line);
+            println!("        {}", trend);
+        }
+    }
+}
+
+fn project_key(project_dir: &Path) -> String {
+    project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf())
+        .display()
+        .to_string()
+}
+
+fn median(durations: &[f64]) -> Option<f64> {
+    if durations.is_empty() {
+        return None;
+    }
+
+    let mut sorted = durations.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[middle - 1] + sorted[middle]) / 2.0)
+    } else {
+        Some(sorted[middle])
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{program_or_default, set_working_dir};
-use anyhow::{ensure, Result};
-use std::path::PathBuf;
+use anyhow::{bail, ensure, Context, Result};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
 
 /// Launch the editor and a terminal at the same working directory.
 #[derive(Debug, clap::Parser)]
@@ -18,6 +21,11 @@ pub struct Launch {
 }
 
 impl Launch {
+    /// Path of the project given to the command.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
     pub fn run(
         self,
         default_editor: Option<String>,
@@ -26,41 +34,63 @@ impl Launch {
         let working_dir = set_working_dir(self.path)?;
 
         let editor_process = if !self.terminal_only {
-            let mut process = program_or_default(self.editor, default_editor, "editor")?;
-            process.current_dir(&working_dir);
-            process.arg(".");
-
-            Some(process)
+            Some(program_or_default(self.editor, default_editor, "editor")?)
         } else {
             None
         };
 
-        let mut terminal_process = program_or_default(self.terminal, default_terminal, "terminal")?;
-        terminal_process.current_dir(&working_dir);
-        terminal_process.args(["--working-directory", "."]);
+        let terminal_process = program_or_default(self.terminal, default_terminal, "terminal")?;
 
-        if let Some(mut editor) = editor_process {
-            let terminal = match terminal_process.spawn() {
-                Ok(child) => Some(child),
-                Err(err) => {
-                    log::error!("an error occurred when launching the terminal: {}", err);
-                    None
-                }
-            };
+        launch(&working_dir, editor_process, terminal_process, false)
+    }
+}
+
+/// Start the editor and the terminal at the working directory.
+///
+/// The terminal is closed when the editor exits. Without an editor, the
+/// terminal is only waited on if `wait` is set.
+pub fn launch(
+    working_dir: &Path,
+    editor_process: Option<process::Command>,
+    mut terminal_process: process::Command,
+    wait: bool,
+) -> Result<()> {
+    terminal_process.current_dir(working_dir);
+    terminal_process.args(["--working-directory", "."]);
 
-            ensure!(
-                editor.status().expect("cannot launch editor").success(),
-                "launch command failed"
-            );
+    if let Some(mut editor) = editor_process {
+        editor.current_dir(working_dir);
+        editor.arg(".");
 
-            if let Some(mut child) = terminal {
-                child.kill()?;
-                child.wait()?;
+        let terminal = match terminal_process.spawn() {
+            Ok(child) => Some(child),
+            Err(err) => {
+                log::error!("an error occurred when launching the terminal: {}", err);
+                None
             }
-        } else {
-            ensure!(terminal_process.spawn().is_ok(), "cannot launch terminal");
+        };
+
+        let status = editor.status();
+
+        if let Some(mut child) = terminal {
+            child.kill()?;
+            child.wait()?;
         }
 
-        Ok(())
+        ensure!(
+            status.context("cannot launch editor")?.success(),
+            "launch command failed"
+        );
+    } else {
+        let mut child = match terminal_process.spawn() {
+            Ok(child) => child,
+            Err(_) => bail!("cannot launch terminal"),
+        };
+
+        if wait {
+            child.wait()?;
+        }
     }
+
+    Ok(())
 }
@@ -1,27 +1,389 @@
-use anyhow::Result;
-use chrono::Datelike;
-use std::{fs, path::Path};
-
-pub fn add_licenses(project_dir_path: &Path, full_name: String) -> Result<()> {
-    let year = chrono::Local::now().date().year();
-
-    fs::write(
-        project_dir_path.join("LICENSE.MIT"),
-        format!(
-            include_str!("../templates/licenses/mit"),
-            year = year,
-            full_name = full_name
-        ),
-    )?;
-
-    fs::write(
-        project_dir_path.join("LICENSE.Apache-2.0"),
-        format!(
-            include_str!("../templates/licenses/apache"),
-            year = year,
-            full_name = full_name,
-        ),
-    )?;
-
-    Ok(())
+use crate::{
+    changes::Changes,
+    template::{self, Variables},
+};
+use anyhow::{bail, Result};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const DEFAULT_LICENSE_EXPRESSION: &str = "MIT OR Apache-2.0";
+
+/// Add the license files to the project.
+///
+/// Existing license files of the same kind are refreshed instead of being
+/// overwritten: their copyright year is extended into a range and the full
+/// name is added to the holders. Returns the names of the license files.
+pub fn add_licenses(
+    changes: &mut Changes,
+    project_dir_path: &Path,
+    variables: &Variables,
+    licenses: &[License],
+) -> Result<Vec<String>> {
+    let existing = existing_licenses(changes, project_dir_path)?;
+    let mut file_names = Vec::new();
+
+    for license in licenses {
+        let mut refreshed = false;
+
+        for (path, content) in existing
+            .iter()
+            .filter(|(_, content)| License::detect(content) == Some(*license))
+        {
+            refreshed = true;
+
+            let new_content = if license.has_copyright() {
+                refresh(content, variables.year, &variables.full_name)
+            } else {
+                content.clone()
+            };
+
+            changes.write(path, new_content);
+
+            if let Some(file_name) = path.file_name() {
+                file_names.push(file_name.to_string_lossy().to_string());
+            }
+        }
+
+        if !refreshed {
+            let path = project_dir_path.join(license.file_name());
+
+            changes.create(path, template::render(license.template_name(), variables)?);
+            file_names.push(license.file_name());
+        }
+    }
+
+    Ok(file_names)
+}
+
+fn existing_licenses(changes: &Changes, project_dir_path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let mut existing = Vec::new();
+
+    for entry in fs::read_dir(project_dir_path)? {
+        let path = entry?.path();
+        let is_license = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_uppercase())
+            .map(|name| {
+                name.starts_with("LICENSE")
+                    || name.starts_with("LICENCE")
+                    || name.starts_with("COPYING")
+            })
+            .unwrap_or(false);
+
+        if is_license && path.is_file() {
+            existing.push((path.clone(), changes.read_to_string(&path)?));
+        }
+    }
+
+    existing.sort();
+
+    Ok(existing)
+}
+
+/// Extend the years and the holders of the copyright lines of a license.
+///
+/// `Copyright (c) 2021 Jane Doe` becomes `Copyright (c) 2021-2026 Jane Doe,
+/// John Doe` when refreshed in 2026 by John Doe.
+fn refresh(content: &str, year: i32, full_name: &str) -> String {
+    let mut refreshed = content
+        .lines()
+        .map(|line| match Copyright::parse(line) {
+            Some(copyright) => copyright.extend(year, full_name).to_string(),
+            None => line.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    if content.ends_with('\n') {
+        refreshed.push('\n');
+    }
+
+    refreshed
+}
+
+/// Copyright line of a license or of a header, such as `Copyright (c)
+/// 2021-2026 Jane Doe, John Doe`.
+#[derive(Debug)]
+pub struct Copyright {
+    prefix: String,
+    first_year: i32,
+    last_year: i32,
+    separator: String,
+    holders: Vec<String>,
+}
+
+impl Copyright {
+    pub fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim_start().strip_prefix("Copyright")?;
+
+        let mut rest = rest.trim_start();
+        for symbol in ["(c)", "(C)", "©"] {
+            if let Some(stripped) = rest.strip_prefix(symbol) {
+                rest = stripped.trim_start();
+            }
+        }
+        let prefix = line[..line.len() - rest.len()].to_string();
+
+        // Only 4-digit tokens are years, `2019 3M Company` is held by
+        // `3M Company`.
+        let mut years = Vec::new();
+        let mut remaining = rest;
+        let years_len = loop {
+            let token = remaining.trim_start_matches(['-', ',', ' ']);
+            let is_year = token.len() >= 4
+                && token.as_bytes()[..4].iter().all(u8::is_ascii_digit)
+                && token[4..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| matches!(c, '-' | ',' | ' '));
+
+            if !is_year {
+                break rest.len() - token.len();
+            }
+
+            years.push(token[..4].parse::<i32>().ok()?);
+            remaining = &token[4..];
+        };
+        let holders = &rest[years_len..];
+
+        let first_year = *years.iter().min()?;
+        let last_year = *years.iter().max()?;
+        let separator = if years_len > 0 && rest[..years_len].trim_end().ends_with(',') {
+            ", ".to_string()
+        } else {
+            " ".to_string()
+        };
+        let holders = holders
+            .split(", ")
+            .map(|holder| holder.trim().to_string())
+            .filter(|holder| !holder.is_empty())
+            .collect();
+
+        Some(Self {
+            prefix,
+            first_year,
+            last_year,
+            separator,
+            holders,
+        })
+    }
+
+    /// Extend the years to `year` and add `full_name` to the holders.
+    pub fn extend(mut self, year: i32, full_name: &str) -> Self {
+        self.first_year = self.first_year.min(year);
+        self.last_year = self.last_year.max(year);
+
+        // A holder can be followed by an email address, as in the headers.
+        if !self.holders.iter().any(|holder| holder.contains(full_name)) {
+            self.holders.push(full_name.to_string());
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for Copyright {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.prefix, self.first_year)?;
+
+        if self.last_year != self.first_year {
+            write!(f, "-{}", self.last_year)?;
+        }
+
+        write!(f, "{}{}", self.separator, self.holders.join(", "))
+    }
+}
+
+/// Get the licenses used in an SPDX license expression.
+///
+/// Operators (`OR`, `AND`), parentheses and `WITH` exceptions are ignored, so
+/// `(MIT OR Apache-2.0) AND BSD-3-Clause` gives the three licenses.
+pub fn parse_expression(expression: &str) -> Result<Vec<License>> {
+    let mut licenses = Vec::new();
+    let mut words = expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == '/')
+        .filter(|word| !word.is_empty());
+
+    while let Some(word) = words.next() {
+        match word {
+            "OR" | "or" | "AND" | "and" => {}
+            "WITH" | "with" => {
+                words.next();
+            }
+            id => {
+                let license = id.parse()?;
+                if !licenses.contains(&license) {
+                    licenses.push(license);
+                }
+            }
+        }
+    }
+
+    if licenses.is_empty() {
+        bail!("no license found in `{}`", expression);
+    }
+
+    Ok(licenses)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum License {
+    Mit,
+    Apache2,
+    Bsd2Clause,
+    Bsd3Clause,
+    Mpl2,
+    Gpl3,
+    Isc,
+    Unlicense,
+}
+
+impl License {
+    /// Detect the kind of a license from its text.
+    pub fn detect(content: &str) -> Option<Self> {
+        let license = if content.contains("Permission is hereby granted, free of charge") {
+            Self::Mit
+        } else if content.contains("Apache License") && content.contains("Version 2.0") {
+            Self::Apache2
+        } else if content.contains("Redistribution and use in source and binary forms") {
+            if content.contains("Neither the name of") {
+                Self::Bsd3Clause
+            } else {
+                Self::Bsd2Clause
+            }
+        } else if content.contains("Mozilla Public License Version 2.0") {
+            Self::Mpl2
+        } else if content.contains("GNU GENERAL PUBLIC LICENSE") && content.contains("Version 3") {
+            Self::Gpl3
+        } else if content.contains("Permission to use, copy, modify, and/or distribute") {
+            Self::Isc
+        } else if content.contains("This is free and unencumbered software") {
+            Self::Unlicense
+        } else {
+            return None;
+        };
+
+        Some(license)
+    }
+
+    /// Whether the license contains a copyright line with the holders.
+    fn has_copyright(&self) -> bool {
+        !matches!(self, Self::Mpl2 | Self::Gpl3 | Self::Unlicense)
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("LICENSE.{}", self)
+    }
+
+    fn template_name(&self) -> &'static str {
+        match self {
+            Self::Mit => "licenses/mit",
+            Self::Apache2 => "licenses/apache",
+            Self::Bsd2Clause => "licenses/bsd-2-clause",
+            Self::Bsd3Clause => "licenses/bsd-3-clause",
+            Self::Mpl2 => "licenses/mpl-2.0",
+            Self::Gpl3 => "licenses/gpl-3.0",
+            Self::Isc => "licenses/isc",
+            Self::Unlicense => "licenses/unlicense",
+        }
+    }
+}
+
+impl FromStr for License {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+
+        let license = match s.as_str() {
+            "mit" => Self::Mit,
+            "apache-2.0" => Self::Apache2,
+            "bsd-2-clause" => Self::Bsd2Clause,
+            "bsd-3-clause" => Self::Bsd3Clause,
+            "mpl-2.0" => Self::Mpl2,
+            "gpl-3.0" | "gpl-3.0-only" | "gpl-3.0-or-later" | "gpl-3.0+" => Self::Gpl3,
+            "isc" => Self::Isc,
+            "unlicense" => Self::Unlicense,
+            _ => bail!("Unsupported license `{}`", s),
+        };
+
+        Ok(license)
+    }
+}
+
+impl fmt::Display for License {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mit => write!(f, "MIT"),
+            Self::Apache2 => write!(f, "Apache-2.0"),
+            Self::Bsd2Clause => write!(f, "BSD-2-Clause"),
+            Self::Bsd3Clause => write!(f, "BSD-3-Clause"),
+            Self::Mpl2 => write!(f, "MPL-2.0"),
+            Self::Gpl3 => write!(f, "GPL-3.0"),
+            Self::Isc => write!(f, "ISC"),
+            Self::Unlicense => write!(f, "Unlicense"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extend(line: &str) -> Option<String> {
+        Copyright::parse(line).map(|copyright| copyright.extend(2026, "Jane Doe").to_string())
+    }
+
+    #[test]
+    fn parse_without_year() {
+        assert!(Copyright::parse("Copyright holders are listed below").is_none());
+        assert!(Copyright::parse("Permission is hereby granted").is_none());
+    }
+
+    #[test]
+    fn extend_year_and_holders() {
+        assert_eq!(
+            extend("Copyright (c) 2021 John Doe").as_deref(),
+            Some("Copyright (c) 2021-2026 John Doe, Jane Doe")
+        );
+        assert_eq!(
+            extend("Copyright (c) 2019-2021 Jane Doe").as_deref(),
+            Some("Copyright (c) 2019-2026 Jane Doe")
+        );
+        assert_eq!(
+            extend("Copyright (c) 2026 Jane Doe").as_deref(),
+            Some("Copyright (c) 2026 Jane Doe")
+        );
+    }
+
+    #[test]
+    fn holder_starting_with_digits() {
+        assert_eq!(
+            extend("Copyright (c) 2019 3M Company").as_deref(),
+            Some("Copyright (c) 2019-2026 3M Company, Jane Doe")
+        );
+    }
+
+    #[test]
+    fn holder_with_email() {
+        assert_eq!(
+            extend("Copyright (c) 2021 Jane Doe <jane@example.com>").as_deref(),
+            Some("Copyright (c) 2021-2026 Jane Doe <jane@example.com>")
+        );
+    }
+
+    #[test]
+    fn refresh_keeps_other_lines() {
+        assert_eq!(
+            refresh(
+                "MIT License\n\nCopyright (c) 2021 Jane Doe\n",
+                2026,
+                "Jane Doe"
+            ),
+            "MIT License\n\nCopyright (c) 2021-2026 Jane Doe\n"
+        );
+    }
 }
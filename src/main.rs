@@ -3,14 +3,28 @@ use std::{env, path, process};
 
 mod add;
 mod background;
+mod changes;
 mod checks;
+mod ci;
 mod config;
+mod diagnostics;
+mod header;
+mod history;
 mod launch;
 mod license;
+mod manifest;
+mod new;
+mod report;
 mod screen;
+mod temp;
+mod template;
+mod watch;
 
 use crate::config::Config;
 
+/// Exit code used when yoz returns an error, the same as the usage errors.
+const ERROR_EXIT_CODE: i32 = 2;
+
 #[derive(Debug, clap::Parser)]
 #[clap(
     about = "This project aims to help my workflow.\n\nDon't expect any kind of stability there."
@@ -19,21 +33,50 @@ enum Opt {
     Add(add::Add),
     Background(background::Background),
     Checks(checks::Checks),
-    Config,
+    Config(config::ConfigCommand),
     Launch(launch::Launch),
+    New(new::New),
     Screen(screen::Screen),
+    Temp(temp::Temp),
+}
+
+impl Opt {
+    /// Path of the project the command applies to, if any.
+    fn path(&self) -> Option<&path::Path> {
+        match self {
+            Self::Add(args) => args.path(),
+            Self::Checks(args) => args.path(),
+            Self::Config(args) => args.path(),
+            Self::Launch(args) => args.path(),
+            Self::New(args) => args.path(),
+            Self::Background(_) | Self::Screen(_) | Self::Temp(_) => None,
+        }
+    }
 }
 
-fn main() -> Result<()> {
+fn main() {
     env_logger::builder()
         .format_timestamp(None)
         .format_module_path(false)
         .filter(Some("yoz"), log::LevelFilter::Debug)
         .init();
 
+    if let Err(err) = run() {
+        eprintln!("Error: {:?}", err);
+        process::exit(ERROR_EXIT_CODE);
+    }
+}
+
+fn run() -> Result<()> {
     let opt: Opt = clap::Parser::parse();
 
-    let config = match Config::get_or_create() {
+    // The config of the project is looked up from the path given to the
+    // command.
+    let project_dir = opt
+        .path()
+        .unwrap_or_else(|| path::Path::new("."))
+        .to_path_buf();
+    let config = match Config::get_or_create(&project_dir) {
         Ok(config) => config,
         Err(err) => {
             bail!("an error occurred with the config file: {}", err);
@@ -41,17 +84,29 @@ fn main() -> Result<()> {
     };
 
     match opt {
-        Opt::Add(args) => args.run(config.default_full_name),
+        Opt::Add(args) => args.run(config.default_full_name, config.default_release_targets),
         Opt::Background(args) => args.run(config.default_bg_file_path, config.default_bg_position),
         Opt::Checks(args) => args.run(
             config.default_check_args,
             config.default_test_args,
             config.default_fmt_args,
             config.default_clippy_args,
+            config.checks,
+            config.features,
         ),
-        Opt::Config => Config::create_from_dot(),
+        Opt::Config(args) => args.run(&project_dir),
         Opt::Launch(args) => args.run(config.default_editor, config.default_terminal),
+        Opt::New(args) => args.run(
+            config.presets,
+            config.default_full_name,
+            config.default_release_targets,
+        ),
         Opt::Screen(args) => args.run(config.main_monitor, config.external_monitor),
+        Opt::Temp(args) => args.run(
+            config.temporary_project_path,
+            config.default_editor,
+            config.default_terminal,
+        ),
     }
 }
 
@@ -0,0 +1,279 @@
+use crate::changes::Changes;
+use anyhow::{bail, ensure, Context, Result};
+use cargo_metadata::{Metadata, Package};
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, Item, Value};
+
+pub fn metadata(project_dir_path: &Path) -> Result<Metadata> {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(project_dir_path.join("Cargo.toml"))
+        .no_deps()
+        .exec()?;
+
+    Ok(metadata)
+}
+
+pub fn package<'a>(metadata: &'a Metadata, project_dir_path: &Path) -> Result<&'a Package> {
+    let manifest_path = project_dir_path.canonicalize()?.join("Cargo.toml");
+
+    match metadata
+        .packages
+        .iter()
+        .find(|package| package.manifest_path == manifest_path)
+    {
+        Some(package) => Ok(package),
+        None => bail!("cannot find a package at {}", project_dir_path.display()),
+    }
+}
+
+/// Get the directories of the members of the workspace containing the given
+/// path.
+///
+/// Only the given packages are returned if any, otherwise every publishable
+/// member is returned.
+pub fn members(project_dir_path: &Path, packages: &[String]) -> Result<Vec<PathBuf>> {
+    let metadata = metadata(project_dir_path)?;
+    let members = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .collect::<Vec<&Package>>();
+
+    for name in packages {
+        ensure!(
+            members.iter().any(|package| &package.name == name),
+            "`{}` is not a member of the workspace",
+            name
+        );
+    }
+
+    let mut dirs = members
+        .into_iter()
+        .filter(|package| {
+            if packages.is_empty() {
+                package.publish.as_ref().is_none_or(|x| !x.is_empty())
+            } else {
+                packages.contains(&package.name)
+            }
+        })
+        .filter_map(|package| package.manifest_path.parent())
+        .map(|dir| dir.to_path_buf().into_std_path_buf())
+        .collect::<Vec<PathBuf>>();
+    dirs.sort();
+
+    Ok(dirs)
+}
+
+/// Get the names of the members of the workspace containing the given path.
+pub fn member_names(project_dir_path: &Path) -> Result<Vec<String>> {
+    let metadata = metadata(project_dir_path)?;
+
+    let mut names = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .map(|package| package.name.clone())
+        .collect::<Vec<String>>();
+    names.sort();
+
+    Ok(names)
+}
+
+/// Get the names and the features of the members of the workspace containing
+/// the given path.
+///
+/// The `default` feature is left out, like cargo-hack does, since it only
+/// enables other features.
+pub fn member_features(project_dir_path: &Path) -> Result<Vec<(String, Vec<String>)>> {
+    let metadata = metadata(project_dir_path)?;
+
+    let mut members = metadata
+        .packages
+        .iter()
+        .filter(|package| metadata.workspace_members.contains(&package.id))
+        .map(|package| {
+            let mut features = package
+                .features
+                .keys()
+                .filter(|feature| *feature != "default")
+                .cloned()
+                .collect::<Vec<String>>();
+            features.sort();
+
+            (package.name.clone(), features)
+        })
+        .collect::<Vec<(String, Vec<String>)>>();
+    members.sort();
+
+    Ok(members)
+}
+
+/// Get the license expression of the package at the given path, if any.
+pub fn license(project_dir_path: &Path) -> Result<Option<String>> {
+    if !project_dir_path.join("Cargo.toml").exists() {
+        return Ok(None);
+    }
+
+    let metadata = metadata(project_dir_path)?;
+    let license = match package(&metadata, project_dir_path) {
+        Ok(package) => package.license.clone(),
+        Err(_) => None,
+    };
+
+    Ok(license)
+}
+
+/// Set the `license` field and add the license files to the `include` field
+/// of the manifest.
+///
+/// When used at the root of a workspace with `all_members`, every member is
+/// updated. Members inheriting their license or their `include` from the
+/// workspace get the `[workspace.package]` of the root manifest updated
+/// instead.
+pub fn update_license(
+    changes: &mut Changes,
+    project_dir_path: &Path,
+    expression: &str,
+    file_names: &[String],
+    all_members: bool,
+) -> Result<()> {
+    let metadata = metadata(project_dir_path)?;
+    let root_manifest_path = metadata.workspace_root.join("Cargo.toml");
+    let all_members = all_members && project_dir_path.canonicalize()? == metadata.workspace_root;
+
+    let manifest_paths = if all_members {
+        metadata
+            .packages
+            .iter()
+            .filter(|package| metadata.workspace_members.contains(&package.id))
+            .map(|package| package.manifest_path.clone())
+            .collect()
+    } else {
+        vec![package(&metadata, project_dir_path)?.manifest_path.clone()]
+    };
+
+    let mut inherited = Inherited::default();
+
+    for manifest_path in manifest_paths.iter().filter(|x| **x != root_manifest_path) {
+        let mut manifest = read_manifest(changes, manifest_path.as_std_path())?;
+
+        if let Some(package) = manifest.get_mut("package") {
+            inherited.merge(update_package(package, expression, file_names));
+        }
+
+        changes.write(manifest_path.clone(), manifest.to_string());
+    }
+
+    if all_members
+        || inherited.license
+        || inherited.include
+        || manifest_paths.contains(&root_manifest_path)
+    {
+        let mut manifest = read_manifest(changes, root_manifest_path.as_std_path())?;
+
+        if manifest_paths.contains(&root_manifest_path) {
+            if let Some(package) = manifest.get_mut("package") {
+                inherited.merge(update_package(package, expression, file_names));
+            }
+        }
+
+        if let Some(workspace_package) = manifest
+            .get_mut("workspace")
+            .and_then(|workspace| workspace.get_mut("package"))
+            .and_then(Item::as_table_like_mut)
+        {
+            if inherited.license || (all_members && workspace_package.contains_key("license")) {
+                set_value(
+                    workspace_package.entry("license").or_insert(Item::None),
+                    expression,
+                );
+                workspace_package.remove("license-file");
+            }
+
+            if all_members || inherited.include {
+                if let Some(include) = workspace_package
+                    .get_mut("include")
+                    .and_then(Item::as_array_mut)
+                {
+                    add_to_array(include, file_names);
+                }
+            }
+        }
+
+        changes.write(root_manifest_path, manifest.to_string());
+    }
+
+    Ok(())
+}
+
+/// Fields of a package inherited from the `[workspace.package]` table.
+#[derive(Debug, Default, Clone, Copy)]
+struct Inherited {
+    license: bool,
+    include: bool,
+}
+
+impl Inherited {
+    fn merge(&mut self, other: Self) {
+        self.license |= other.license;
+        self.include |= other.include;
+    }
+}
+
+/// Update the `[package]` table of a manifest.
+///
+/// Returns the fields inherited from the workspace, which are left untouched.
+fn update_package(package: &mut Item, expression: &str, file_names: &[String]) -> Inherited {
+    let package = match package.as_table_like_mut() {
+        Some(package) => package,
+        None => return Inherited::default(),
+    };
+
+    let inherited = Inherited {
+        license: is_inherited(package.get("license")),
+        include: is_inherited(package.get("include")),
+    };
+
+    if !inherited.license {
+        set_value(package.entry("license").or_insert(Item::None), expression);
+        package.remove("license-file");
+    }
+
+    if let Some(include) = package.get_mut("include").and_then(Item::as_array_mut) {
+        add_to_array(include, file_names);
+    }
+
+    inherited
+}
+
+fn read_manifest(changes: &Changes, manifest_path: &Path) -> Result<DocumentMut> {
+    changes
+        .read_to_string(manifest_path)?
+        .parse::<DocumentMut>()
+        .with_context(|| format!("cannot parse {}", manifest_path.display()))
+}
+
+fn is_inherited(item: Option<&Item>) -> bool {
+    item.and_then(|item| item.get("workspace"))
+        .and_then(Item::as_bool)
+        .unwrap_or(false)
+}
+
+fn set_value(item: &mut Item, value: &str) {
+    match item.as_value_mut() {
+        Some(old) => {
+            let decor = old.decor().clone();
+            *old = Value::from(value);
+            *old.decor_mut() = decor;
+        }
+        None => *item = toml_edit::value(value),
+    }
+}
+
+fn add_to_array(array: &mut Array, values: &[String]) {
+    for value in values {
+        if !array.iter().any(|item| item.as_str() == Some(value)) {
+            array.push(value.as_str());
+        }
+    }
+}
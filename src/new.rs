@@ -0,0 +1,153 @@
+use crate::add::Add;
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Create a new Rust project ready to be published.
+///
+/// The project is created with `cargo new`, the content of the preset is
+/// added to it and an initial commit is made.
+#[derive(Debug, clap::Parser)]
+pub struct New {
+    /// Name of the project.
+    name: String,
+    /// Path of the project.
+    ///
+    /// Defaults to a directory with the name of the project in the current
+    /// directory.
+    #[clap(short = 'p', long)]
+    path: Option<PathBuf>,
+    /// Create a library instead of a binary.
+    #[clap(long)]
+    lib: bool,
+    /// Name of the preset of the config file used to generate the content.
+    #[clap(long, default_value = "default")]
+    preset: String,
+    /// Do not initialize a git repository.
+    #[clap(long)]
+    no_git: bool,
+}
+
+impl New {
+    /// Path of the project given to the command.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn run(
+        self,
+        presets: BTreeMap<String, Preset>,
+        default_full_name: Option<String>,
+        default_release_targets: Vec<String>,
+    ) -> Result<()> {
+        let preset = match presets.get(&self.preset) {
+            Some(preset) => preset.clone(),
+            None if self.preset == "default" => Preset::standard(),
+            None => bail!(
+                "Please configure the preset `{}` in your config file",
+                self.preset
+            ),
+        };
+
+        let project_dir = match self.path {
+            Some(path) => path,
+            None => env::current_dir()?.join(&self.name),
+        };
+        ensure!(
+            !project_dir.exists(),
+            "{} already exists",
+            project_dir.display()
+        );
+
+        // The project did not exist, it is removed if anything fails so the
+        // command can be run again.
+        let create = || -> Result<()> {
+            let mut cargo_new = process::Command::new("cargo");
+            cargo_new
+                .arg("new")
+                .args(["--vcs", "none"])
+                .args(["--name", &self.name])
+                .arg(&project_dir);
+            if self.lib {
+                cargo_new.arg("--lib");
+            }
+            ensure!(cargo_new.status()?.success(), "cannot create the project");
+
+            Add::from_preset(project_dir.clone(), self.lib, preset)
+                .run(default_full_name, default_release_targets)?;
+
+            if !self.no_git {
+                for args in [
+                    vec!["init", "--quiet"],
+                    vec!["add", "--all"],
+                    vec!["commit", "--quiet", "--message", "Initial commit"],
+                ] {
+                    ensure!(
+                        process::Command::new("git")
+                            .current_dir(&project_dir)
+                            .args(&args)
+                            .status()?
+                            .success(),
+                        "`git {}` failed",
+                        args[0]
+                    );
+                }
+            }
+
+            Ok(())
+        };
+
+        if let Err(err) = create() {
+            if project_dir.exists() {
+                log::info!("Deleting {}", project_dir.display());
+                fs::remove_dir_all(&project_dir)?;
+            }
+
+            return Err(err);
+        }
+
+        log::info!("Project created at {}", project_dir.display());
+
+        Ok(())
+    }
+}
+
+/// Content added to the projects created with `yoz new`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    #[serde(default)]
+    pub licenses: bool,
+    pub license: Option<String>,
+    #[serde(default)]
+    pub headers: bool,
+    #[serde(default)]
+    pub ci: bool,
+    #[serde(default)]
+    pub no_windows: bool,
+    #[serde(default)]
+    pub no_osx: bool,
+    #[serde(default)]
+    pub readme: bool,
+    #[serde(default)]
+    pub gitignore: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub release_targets: Vec<String>,
+}
+
+impl Preset {
+    /// Preset used when no `default` preset is configured.
+    pub fn standard() -> Self {
+        Self {
+            licenses: true,
+            ci: true,
+            readme: true,
+            gitignore: true,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::checks::{CheckError, CheckOutput};
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::{fmt, fs, path::Path, str::FromStr};
+
+/// Format of the reports of `yoz checks`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+
+        let format = match s.as_str() {
+            "json" => Self::Json,
+            "junit" => Self::Junit,
+            _ => bail!("Cannot parse report format from {}", s),
+        };
+
+        Ok(format)
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Junit => write!(f, "junit"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    name: String,
+    timestamp: String,
+    checks: Vec<Entry>,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    name: String,
+    command: String,
+    status: Status,
+    duration: f64,
+    output: String,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Success,
+    Failure,
+    Error,
+}
+
+/// Write the report of a run of the checks of the project with the given
+/// name.
+pub fn write(
+    format: ReportFormat,
+    path: &Path,
+    name: &str,
+    outputs: &[CheckOutput],
+    errors: &[CheckError],
+) -> Result<()> {
+    let mut checks = outputs
+        .iter()
+        .map(|output| Entry {
+            name: output.label(),
+            command: output.command_string.clone(),
+            status: if output.success {
+                Status::Success
+            } else {
+                Status::Failure
+            },
+            duration: output.duration.as_secs_f64(),
+            output: output.output.clone(),
+        })
+        .collect::<Vec<Entry>>();
+    checks.extend(errors.iter().map(|error| Entry {
+        name: error.label(),
+        command: error.command_string.clone(),
+        status: Status::Error,
+        duration: 0.0,
+        output: error.error.clone(),
+    }));
+
+    let report = Report {
+        name: name.to_string(),
+        timestamp: chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+        checks,
+    };
+
+    let content = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+        ReportFormat::Junit => junit(&report),
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+
+    Ok(())
+}
+
+fn junit(report: &Report) -> String {
+    let count = |status| {
+        report
+            .checks
+            .iter()
+            .filter(|check| check.status == status)
+            .count()
+    };
+    let tests = report.checks.len();
+    let failures = count(Status::Failure);
+    let errors = count(Status::Error);
+    let time = report
+        .checks
+        .iter()
+        .map(|check| check.duration)
+        .sum::<f64>();
+    let name = escape(&format!("yoz checks {}", report.name));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        name, tests, failures, errors, time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\" \
+        timestamp=\"{}\">\n",
+        name, tests, failures, errors, time, report.timestamp
+    ));
+
+    for check in &report.checks {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"yoz.checks\" time=\"{:.3}\">\n",
+            escape(&check.name),
+            check.duration
+        ));
+
+        match check.status {
+            Status::Success => {}
+            Status::Failure => xml.push_str(&format!(
+                "      <failure message=\"`{}` failed\">{}</failure>\n",
+                escape(&check.command),
+                escape(&check.output)
+            )),
+            Status::Error => xml.push_str(&format!(
+                "      <error message=\"`{}` could not be run\">{}</error>\n",
+                escape(&check.command),
+                escape(&check.output)
+            )),
+        }
+
+        if check.status != Status::Error {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                escape(&check.output)
+            ));
+        }
+
+        xml.push_str("    </testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+
+    xml
+}
+
+/// Escape the text for XML, dropping the control characters that XML does
+/// not allow such as the ANSI escape codes of the colored output.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,381 @@
+use crate::{launch::launch, program_or_default, value_or_default};
+use anyhow::{bail, ensure, Context, Result};
+use chrono::{DateTime, Local};
+use indicatif::HumanBytes;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use toml_edit::DocumentMut;
+use walkdir::WalkDir;
+
+/// Prefix of the names of the temporary projects created by yoz.
+const PROJECT_PREFIX: &str = "tmp-";
+
+/// Create a temporary project and open it.
+///
+/// The project is created in the `temporary_project_path` of the config file
+/// and deleted when the editor exits.
+#[derive(Debug, clap::Parser)]
+#[clap(args_conflicts_with_subcommands = true)]
+pub struct Temp {
+    #[clap(subcommand)]
+    command: Option<TempCommand>,
+    /// Dependencies added to the project.
+    ///
+    /// Use `name=version` to select a version, the latest is used otherwise.
+    dependencies: Vec<String>,
+    /// Create a library instead of a binary.
+    #[clap(long)]
+    lib: bool,
+    /// Keep the project when the editor exits.
+    #[clap(long)]
+    keep: bool,
+    /// Program to use as the editor.
+    #[clap(long)]
+    editor: Option<String>,
+    /// Program to use as the terminal.
+    #[clap(long)]
+    terminal: Option<String>,
+    /// Start only a terminal in the project.
+    #[clap(short = 't', long)]
+    terminal_only: bool,
+}
+
+impl Temp {
+    pub fn run(
+        self,
+        temporary_project_path: Option<PathBuf>,
+        default_editor: Option<String>,
+        default_terminal: Option<String>,
+    ) -> Result<()> {
+        let temporary_project_path =
+            value_or_default(None, temporary_project_path, "temporary_project_path")?;
+        fs::create_dir_all(&temporary_project_path)?;
+
+        match self.command {
+            Some(TempCommand::List) => return list(&temporary_project_path),
+            Some(TempCommand::Clean { older_than, .. }) => {
+                return clean(&temporary_project_path, older_than)
+            }
+            Some(TempCommand::Promote {
+                name,
+                destination,
+                package_name,
+            }) => return promote(&temporary_project_path, &name, &destination, package_name),
+            None => {}
+        }
+
+        let editor_process = if !self.terminal_only {
+            Some(program_or_default(self.editor, default_editor, "editor")?)
+        } else {
+            None
+        };
+        let terminal_process = program_or_default(self.terminal, default_terminal, "terminal")?;
+
+        let name = format!(
+            "{}{}",
+            PROJECT_PREFIX,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let mut project_dir = temporary_project_path.join(&name);
+        let mut i = 1;
+        while project_dir.exists() {
+            i += 1;
+            project_dir = temporary_project_path.join(format!("{}-{}", name, i));
+        }
+
+        // The project is deleted on every path from now on, errors and
+        // panics included.
+        let _project = ProjectGuard {
+            path: project_dir.clone(),
+            keep: self.keep,
+        };
+
+        let mut cargo_new = process::Command::new("cargo");
+        cargo_new
+            .arg("new")
+            .args(["--vcs", "none"])
+            .arg(&project_dir);
+        if self.lib {
+            cargo_new.arg("--lib");
+        }
+        ensure!(
+            cargo_new.status()?.success(),
+            "cannot create the temporary project"
+        );
+
+        if !self.dependencies.is_empty() {
+            add_dependencies(&project_dir, &self.dependencies)?;
+        }
+
+        launch(&project_dir, editor_process, terminal_process, true)
+    }
+}
+
+/// Delete the temporary project when dropped, unless it is kept.
+struct ProjectGuard {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl Drop for ProjectGuard {
+    fn drop(&mut self) {
+        if !self.path.exists() {
+            return;
+        }
+
+        if self.keep {
+            log::info!("Project kept at {}", self.path.display());
+        } else {
+            log::info!("Deleting {}", self.path.display());
+            if let Err(err) = fs::remove_dir_all(&self.path) {
+                log::error!("cannot delete {}: {}", self.path.display(), err);
+            }
+        }
+    }
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum TempCommand {
+    /// List the temporary projects with their size and last modification.
+    List,
+    /// Delete the temporary projects.
+    ///
+    /// Only the projects created by yoz are deleted, the other directories of
+    /// `temporary_project_path` are left untouched.
+    Clean {
+        /// Only delete the projects not modified for this duration, e.g.
+        /// `12h` or `7d`.
+        #[clap(long, required_unless_present = "all")]
+        older_than: Option<Age>,
+        /// Delete all the projects.
+        #[clap(long, conflicts_with = "older-than")]
+        all: bool,
+    },
+    /// Move a temporary project to another location and rename the package.
+    Promote {
+        /// Name of the temporary project.
+        name: String,
+        /// Path where the project is moved.
+        destination: PathBuf,
+        /// New name of the package.
+        ///
+        /// Defaults to the name of the destination directory.
+        #[clap(long = "name")]
+        package_name: Option<String>,
+    },
+}
+
+fn list(temporary_project_path: &Path) -> Result<()> {
+    let projects = projects(temporary_project_path)?;
+
+    if projects.is_empty() {
+        println!("No temporary project");
+    }
+
+    for project in projects {
+        println!(
+            "{:<30} {:>10}  {}",
+            project.name,
+            HumanBytes(project.size).to_string(),
+            DateTime::<Local>::from(project.modified).format("%Y-%m-%d %H:%M"),
+        );
+    }
+
+    Ok(())
+}
+
+fn clean(temporary_project_path: &Path, older_than: Option<Age>) -> Result<()> {
+    let now = SystemTime::now();
+    let mut count = 0;
+
+    for project in projects(temporary_project_path)? {
+        let age = now.duration_since(project.modified).unwrap_or_default();
+
+        if older_than
+            .as_ref()
+            .is_none_or(|older_than| age >= older_than.0)
+        {
+            log::info!("Deleting {}", project.path.display());
+            fs::remove_dir_all(&project.path)?;
+            count += 1;
+        }
+    }
+
+    log::info!("{} project(s) deleted", count);
+
+    Ok(())
+}
+
+fn promote(
+    temporary_project_path: &Path,
+    name: &str,
+    destination: &Path,
+    package_name: Option<String>,
+) -> Result<()> {
+    let project_dir = match projects(temporary_project_path)?
+        .into_iter()
+        .find(|project| project.name == name)
+    {
+        Some(project) if project.path.join("Cargo.toml").exists() => project.path,
+        _ => bail!("`{}` is not a temporary project", name),
+    };
+    ensure!(
+        !destination.exists(),
+        "{} already exists",
+        destination.display()
+    );
+
+    let package_name = match (package_name, destination.file_name()) {
+        (Some(package_name), _) => package_name,
+        (None, Some(file_name)) => file_name.to_string_lossy().to_string(),
+        (None, None) => bail!("invalid destination {}", destination.display()),
+    };
+    ensure!(
+        is_valid_package_name(&package_name),
+        "`{}` is not a valid package name, use `--name` to choose one",
+        package_name
+    );
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    if fs::rename(&project_dir, destination).is_err() {
+        copy_dir(&project_dir, destination)?;
+        fs::remove_dir_all(&project_dir)?;
+    }
+
+    let manifest_path = destination.join("Cargo.toml");
+    let mut manifest = read_manifest(&manifest_path)?;
+    manifest["package"]["name"] = toml_edit::value(package_name.as_str());
+    fs::write(manifest_path, manifest.to_string())?;
+
+    log::info!("`{}` moved to {}", name, destination.display());
+
+    Ok(())
+}
+
+/// Check the name like cargo does: ASCII letters, digits, `-` and `_`, not
+/// starting with a digit.
+fn is_valid_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Debug)]
+struct Project {
+    name: String,
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn projects(temporary_project_path: &Path) -> Result<Vec<Project>> {
+    let mut projects = Vec::new();
+
+    for entry in fs::read_dir(temporary_project_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_dir() || !name.starts_with(PROJECT_PREFIX) {
+            continue;
+        }
+
+        let mut size = 0;
+        let mut modified = entry.metadata()?.modified()?;
+        for file in WalkDir::new(entry.path()) {
+            let metadata = file?.metadata()?;
+
+            size += metadata.len();
+            modified = modified.max(metadata.modified()?);
+        }
+
+        projects.push(Project {
+            name,
+            path: entry.path(),
+            size,
+            modified,
+        });
+    }
+
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(projects)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let path = to.join(entry.path().strip_prefix(from)?);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(path)?;
+        } else {
+            fs::copy(entry.path(), path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Duration written with a unit, e.g. `30m`, `12h` or `7d`.
+#[derive(Debug)]
+struct Age(Duration);
+
+impl FromStr for Age {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (value, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let value: u64 = value
+            .parse()
+            .with_context(|| format!("Cannot parse duration from {}", s))?;
+
+        let seconds = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => bail!("Cannot parse duration from {}, use s, m, h, d or w", s),
+        };
+
+        let seconds = match value.checked_mul(seconds) {
+            Some(seconds) => seconds,
+            None => bail!("Cannot parse duration from {}, the value is too large", s),
+        };
+
+        Ok(Self(Duration::from_secs(seconds)))
+    }
+}
+
+fn read_manifest(manifest_path: &Path) -> Result<DocumentMut> {
+    fs::read_to_string(manifest_path)?
+        .parse::<DocumentMut>()
+        .with_context(|| format!("cannot parse {}", manifest_path.display()))
+}
+
+fn add_dependencies(project_dir: &Path, dependencies: &[String]) -> Result<()> {
+    let manifest_path = project_dir.join("Cargo.toml");
+    let mut manifest = read_manifest(&manifest_path)?;
+
+    for dependency in dependencies {
+        let (name, version) = match dependency.split_once('=') {
+            Some((name, version)) => (name, version),
+            None => (dependency.as_str(), "*"),
+        };
+
+        manifest["dependencies"][name] = toml_edit::value(version);
+    }
+
+    fs::write(manifest_path, manifest.to_string())?;
+
+    Ok(())
+}
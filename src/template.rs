@@ -0,0 +1,238 @@
+use crate::manifest;
+use anyhow::{bail, Result};
+use chrono::Datelike;
+use std::{fs, path::Path};
+
+/// Variables available in every template.
+///
+/// Templates reference them with `{year}`, `{full_name}`, `{name}`,
+/// `{repository}` and `{description}`. Some generators provide additional
+/// variables (`{os}` for the CI workflows, `{bin}` and `{matrix}` for the
+/// release workflow). Use `{{` and `}}` for literal braces.
+#[derive(Debug, Clone)]
+pub struct Variables {
+    pub year: i32,
+    pub full_name: String,
+    pub name: String,
+    pub repository: String,
+    pub description: String,
+    extra: Vec<(&'static str, String)>,
+}
+
+impl Variables {
+    /// Create the variables of the project at the given path.
+    ///
+    /// `name`, `repository` and `description` are read from the manifest
+    /// when the path points to a Rust package, otherwise they are empty.
+    pub fn new(project_dir_path: &Path, full_name: String) -> Self {
+        let mut variables = Self {
+            year: chrono::Local::now().date().year(),
+            full_name,
+            name: String::new(),
+            repository: String::new(),
+            description: String::new(),
+            extra: Vec::new(),
+        };
+
+        if project_dir_path.join("Cargo.toml").exists() {
+            if let Ok(metadata) = manifest::metadata(project_dir_path) {
+                if let Ok(package) = manifest::package(&metadata, project_dir_path) {
+                    variables.name = package.name.clone();
+                    variables.repository = package.repository.clone().unwrap_or_default();
+                    variables.description = package.description.clone().unwrap_or_default();
+                }
+            }
+        }
+
+        variables
+    }
+
+    pub fn with(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.extra.push((key, value.into()));
+        self
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "year" => self.year.to_string(),
+            "full_name" => self.full_name.clone(),
+            "name" => self.name.clone(),
+            "repository" => self.repository.clone(),
+            "description" => self.description.clone(),
+            _ => self
+                .extra
+                .iter()
+                .rev()
+                .find(|(extra_key, _)| *extra_key == key)
+                .map(|(_, value)| value.clone())?,
+        };
+
+        Some(value)
+    }
+}
+
+/// Render the template with the given name.
+///
+/// The template is looked up in the `templates` directory of the XDG data
+/// directory of yoz (`~/.local/share/yoz/templates` by default) first, then
+/// in the templates embedded in the binary.
+pub fn render(name: &str, variables: &Variables) -> Result<String> {
+    let user_template_path =
+        xdg::BaseDirectories::with_prefix("yoz")?.find_data_file(Path::new("templates").join(name));
+
+    let template = match user_template_path {
+        Some(path) => {
+            log::debug!("Using template {}", path.display());
+            fs::read_to_string(path)?
+        }
+        None => match embedded(name) {
+            Some(template) => template.to_string(),
+            None => bail!("unknown template `{}`", name),
+        },
+    };
+
+    substitute(name, &template, variables)
+}
+
+fn embedded(name: &str) -> Option<&'static str> {
+    let template = match name {
+        "licenses/mit" => include_str!("../templates/licenses/mit"),
+        "licenses/apache" => include_str!("../templates/licenses/apache"),
+        "licenses/bsd-2-clause" => include_str!("../templates/licenses/bsd-2-clause"),
+        "licenses/bsd-3-clause" => include_str!("../templates/licenses/bsd-3-clause"),
+        "licenses/mpl-2.0" => include_str!("../templates/licenses/mpl-2.0"),
+        "licenses/gpl-3.0" => include_str!("../templates/licenses/gpl-3.0"),
+        "licenses/isc" => include_str!("../templates/licenses/isc"),
+        "licenses/unlicense" => include_str!("../templates/licenses/unlicense"),
+        "ci/pr.yml" => include_str!("../templates/ci/pr.yml"),
+        "ci/pr-lib.yml" => include_str!("../templates/ci/pr-lib.yml"),
+        "ci/main.yml" => include_str!("../templates/ci/main.yml"),
+        "ci/main-lib.yml" => include_str!("../templates/ci/main-lib.yml"),
+        "ci/publish.yml" => include_str!("../templates/ci/publish.yml"),
+        "ci/release.yml" => include_str!("../templates/ci/release.yml"),
+        "readme" => include_str!("../templates/readme"),
+        "gitignore" => include_str!("../templates/gitignore"),
+        _ => return None,
+    };
+
+    Some(template)
+}
+
+fn substitute(name: &str, template: &str, variables: &Variables) -> Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut key = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => key.push(c),
+                        None => bail!("unclosed variable in template `{}`", name),
+                    }
+                }
+
+                match variables.get(&key) {
+                    Some(value) => rendered.push_str(&value),
+                    None => bail!("unknown variable `{}` in template `{}`", key, name),
+                }
+            }
+            c => rendered.push(c),
+        }
+    }
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> Variables {
+        Variables {
+            year: 2026,
+            full_name: "Jane Doe".to_string(),
+            name: "yoz".to_string(),
+            repository: String::new(),
+            description: String::new(),
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn substitute_variables() {
+        assert_eq!(
+            substitute("test", "Copyright (c) {year} {full_name}", &variables()).unwrap(),
+            "Copyright (c) 2026 Jane Doe"
+        );
+    }
+
+    #[test]
+    fn substitute_escaped_braces() {
+        assert_eq!(
+            substitute("test", "runs-on: ${{{{ matrix.os }}}}", &variables()).unwrap(),
+            "runs-on: ${{ matrix.os }}"
+        );
+        assert_eq!(
+            substitute("test", "{{name}} is {name}", &variables()).unwrap(),
+            "{name} is yoz"
+        );
+    }
+
+    #[test]
+    fn substitute_unknown_variable() {
+        let err = substitute("test", "{nope}", &variables()).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "unknown variable `nope` in template `test`"
+        );
+    }
+
+    #[test]
+    fn substitute_unclosed_variable() {
+        let err = substitute("test", "{name", &variables()).unwrap_err();
+
+        assert_eq!(err.to_string(), "unclosed variable in template `test`");
+    }
+
+    #[test]
+    fn last_extra_variable_wins() {
+        let variables = variables().with("os", "linux").with("os", "windows");
+
+        assert_eq!(substitute("test", "{os}", &variables).unwrap(), "windows");
+    }
+
+    #[test]
+    fn embedded_ci_templates() {
+        let variables = variables()
+            .with("os", "ubuntu-latest")
+            .with("bin", "yoz")
+            .with("matrix", "");
+
+        for name in [
+            "ci/pr.yml",
+            "ci/pr-lib.yml",
+            "ci/main.yml",
+            "ci/main-lib.yml",
+            "ci/publish.yml",
+            "ci/release.yml",
+        ] {
+            let template = embedded(name).unwrap();
+            let rendered = substitute(name, template, &variables).unwrap();
+
+            assert!(rendered.contains("${{ "), "{}", name);
+            assert!(!rendered.contains("{{{{"), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use ignore::gitignore::GitignoreBuilder;
+use notify::{EventKind, RecursiveMode, Watcher as _};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    time::Duration,
+};
+
+/// Time without changes to wait for before considering the changes done.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+/// Watch the changes of the files of a project.
+///
+/// Hidden files, `target` directories, the given ignored files and the files
+/// ignored by the `.gitignore` files of the project, of its subdirectories or
+/// of its parents are not watched.
+pub struct Watcher {
+    _watcher: notify::RecommendedWatcher,
+    receiver: mpsc::Receiver<PathBuf>,
+}
+
+impl Watcher {
+    /// Start watching the project, setting `changed` on every change.
+    ///
+    /// The `ignored` files, such as the files written by the checks, are not
+    /// watched.
+    pub fn new(
+        project_dir_path: &Path,
+        ignored: Vec<PathBuf>,
+        changed: Arc<AtomicBool>,
+    ) -> Result<Self> {
+        let root = project_dir_path.canonicalize()?;
+        let current_dir = env::current_dir()?;
+        let ignored = ignored
+            .into_iter()
+            .map(|path| current_dir.join(path))
+            .collect::<Vec<PathBuf>>();
+
+        let (sender, receiver) = mpsc::channel();
+        let event_root = root.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::error!("cannot watch the project: {}", err);
+                        return;
+                    }
+                };
+
+                if !matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    return;
+                }
+
+                for path in event.paths {
+                    if !is_ignored(&event_root, &ignored, &path) {
+                        changed.store(true, Ordering::SeqCst);
+                        let _ = sender.send(path);
+                    }
+                }
+            })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// Wait for the next change.
+    pub fn wait(&self) -> Result<()> {
+        let path = self.receiver.recv()?;
+        log::debug!("{} changed", path.display());
+
+        Ok(())
+    }
+
+    /// Wait until nothing changed for a short delay.
+    pub fn debounce(&self) {
+        while self.receiver.recv_timeout(DEBOUNCE_DELAY).is_ok() {}
+    }
+
+    /// Forget the changes received until now, such as the changes made by a
+    /// run of the checks.
+    pub fn clear(&self) {
+        self.debounce();
+    }
+}
+
+fn is_ignored(root: &Path, ignored: &[PathBuf], path: &Path) -> bool {
+    let relative_path = match path.strip_prefix(root) {
+        Ok(relative_path) => relative_path,
+        Err(_) => return true,
+    };
+
+    if relative_path.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name.starts_with('.') || name == "target"
+    }) {
+        return true;
+    }
+
+    if ignored.iter().any(|ignored| is_same_file(ignored, path)) {
+        return true;
+    }
+
+    // The `.gitignore` files are loaded on every change to take their own
+    // changes into account. The closest one takes precedence.
+    for dir in path.ancestors().skip(1) {
+        let gitignore_path = dir.join(".gitignore");
+        if !gitignore_path.is_file() {
+            continue;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        builder.add(gitignore_path);
+        let gitignore = match builder.build() {
+            Ok(gitignore) => gitignore,
+            Err(_) => continue,
+        };
+
+        let matched = gitignore.matched_path_or_any_parents(path, path.is_dir());
+        if !matched.is_none() {
+            return matched.is_ignore();
+        }
+    }
+
+    false
+}
+
+/// Compare the paths by their canonical parent since the files may not exist.
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    if a.file_name() != b.file_name() {
+        return false;
+    }
+
+    match (
+        a.parent().and_then(|x| x.canonicalize().ok()),
+        b.parent().and_then(|x| x.canonicalize().ok()),
+    ) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}